@@ -0,0 +1,186 @@
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+/// Number of minor units (öre, cents) in one major unit.
+const MINOR_PER_MAJOR: i64 = 100;
+
+/// A monetary amount with two decimals, stored in the smallest currency unit.
+///
+/// Parses and prints decimal strings, i.e. `125,50`, `125.50` or `-3`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Amount(i64);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseAmountError(String);
+
+impl Display for ParseAmountError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid amount '{}'", self.0)
+    }
+}
+
+impl std::error::Error for ParseAmountError {}
+
+impl Amount {
+    pub const ZERO: Amount = Amount(0);
+
+    /// Creates an amount from the smallest currency unit, i.e. `12550` is `125.50`.
+    pub const fn from_minor(minor: i64) -> Self {
+        Amount(minor)
+    }
+
+    /// The amount in the smallest currency unit.
+    pub const fn minor(self) -> i64 {
+        self.0
+    }
+
+    pub const fn is_negative(self) -> bool {
+        self.0 < 0
+    }
+
+    pub const fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn checked_add(self, other: Amount) -> Option<Amount> {
+        self.0.checked_add(other.0).map(Amount)
+    }
+
+    pub fn checked_sub(self, other: Amount) -> Option<Amount> {
+        self.0.checked_sub(other.0).map(Amount)
+    }
+
+    pub fn checked_neg(self) -> Option<Amount> {
+        self.0.checked_neg().map(Amount)
+    }
+
+    pub fn checked_abs(self) -> Option<Amount> {
+        self.0.checked_abs().map(Amount)
+    }
+}
+
+impl FromStr for Amount {
+    type Err = ParseAmountError;
+
+    /// Accepts an optional sign, an integer part and up to two decimals separated by `,` or `.`.
+    /// Spaces and underscores may be used as thousand separators.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseAmountError(s.to_string());
+        let cleaned: String = s.chars().filter(|c| *c != ' ' && *c != '_').collect();
+        let (negative, digits) = match cleaned.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, cleaned.strip_prefix('+').unwrap_or(&cleaned)),
+        };
+        let (major, minor) = match digits.split_once(['.', ',']) {
+            Some((major, minor)) => (major, minor),
+            None => (digits, ""),
+        };
+        let all_digits = |p: &str| p.chars().all(|c| c.is_ascii_digit());
+        if major.is_empty() || minor.len() > 2 || !all_digits(major) || !all_digits(minor) {
+            return Err(err());
+        }
+        let major: i128 = major.parse().map_err(|_| err())?;
+        let minor: i128 = match minor.len() {
+            0 => 0,
+            1 => minor.parse::<i128>().map_err(|_| err())? * 10,
+            _ => minor.parse().map_err(|_| err())?,
+        };
+        let value = major
+            .checked_mul(MINOR_PER_MAJOR as i128)
+            .and_then(|value| value.checked_add(minor))
+            .ok_or_else(err)?;
+        let value = if negative { -value } else { value };
+        i64::try_from(value).map(Amount).map_err(|_| err())
+    }
+}
+
+impl Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let abs = self.0.unsigned_abs();
+        let per = MINOR_PER_MAJOR as u64;
+        f.pad(&format!("{}{}.{:02}", sign, abs / per, abs % per))
+    }
+}
+
+/// Amounts are decimal strings, as in the JSON output.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Amount {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Amount {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck_macros::quickcheck;
+
+    #[test]
+    fn parses_decimal_strings() {
+        assert_eq!("125,50".parse(), Ok(Amount::from_minor(12550)));
+        assert_eq!("125.5".parse(), Ok(Amount::from_minor(12550)));
+        assert_eq!("125".parse(), Ok(Amount::from_minor(12500)));
+        assert_eq!("-0,05".parse(), Ok(Amount::from_minor(-5)));
+        assert_eq!("1 250,00".parse(), Ok(Amount::from_minor(125000)));
+    }
+
+    #[test]
+    fn rejects_malformed_strings() {
+        for s in ["", "-", ",50", "1,234", "1.2.3", "12a", "--1"] {
+            assert!(s.parse::<Amount>().is_err(), "{} should not parse", s);
+        }
+    }
+
+    #[test]
+    fn rejects_amounts_too_large() {
+        for s in [
+            "92233720368547758.08",
+            "1701411834604692317316873037158841057.99",
+            "-1701411834604692317316873037158841057.99",
+            "1701411834604692317316873037158841058",
+            "99999999999999999999999999999999999999999",
+        ] {
+            assert!(s.parse::<Amount>().is_err(), "{} should not parse", s);
+        }
+        assert_eq!(
+            "92233720368547758.07".parse(),
+            Ok(Amount::from_minor(i64::MAX))
+        );
+    }
+
+    #[test]
+    fn prints_two_decimals() {
+        assert_eq!(Amount::from_minor(12550).to_string(), "125.50");
+        assert_eq!(Amount::from_minor(-5).to_string(), "-0.05");
+        assert_eq!(format!("{:>8}", Amount::from_minor(100)), "    1.00");
+    }
+
+    #[test]
+    fn arithmetic_is_checked() {
+        assert_eq!(
+            Amount::from_minor(i64::MAX).checked_add(Amount::from_minor(1)),
+            None
+        );
+        assert_eq!(
+            Amount::from_minor(1).checked_sub(Amount::from_minor(3)),
+            Some(Amount::from_minor(-2))
+        );
+    }
+
+    #[quickcheck]
+    fn display_round_trips(minor: i64) -> bool {
+        let amount = Amount::from_minor(minor);
+        amount.to_string().parse() == Ok(amount)
+    }
+}
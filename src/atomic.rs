@@ -0,0 +1,119 @@
+use std::{
+    fs::{File, remove_file, rename},
+    io::{Error, ErrorKind, Result, Write},
+    path::{Path, PathBuf},
+    process,
+};
+
+use crate::Error as LedgerError;
+
+/// Name of the lock file in the ledger directory.
+const LOCK: &str = "LOCK";
+
+/// Writes a file by writing a temporary file next to it and renaming it into place.
+///
+/// Readers see either the old or the new content, never a partial write, and both the
+/// file and its directory are synced so the new content survives a crash once this returns.
+pub(crate) fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "Can't write to a directory"))?;
+    let tmp = dir.join(format!(
+        ".{}.{}.tmp",
+        file_name.to_string_lossy(),
+        process::id()
+    ));
+    let result = File::create(&tmp).and_then(|mut file| {
+        file.write_all(contents.as_ref())?;
+        file.sync_all()?;
+        rename(&tmp, path)
+    });
+    if result.is_err() {
+        let _ = remove_file(&tmp);
+    }
+    result?;
+    sync_dir(dir)
+}
+
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> Result<()> {
+    File::open(dir)?.sync_all()
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) -> Result<()> {
+    Ok(())
+}
+
+/// An exclusive lock on a ledger directory, held while the ledger is changed.
+///
+/// The lock is a `LOCK` file holding the process id, created only if it doesn't exist and
+/// removed on drop. A process that crashes leaves it behind and it then has to be removed
+/// by hand.
+#[derive(Debug)]
+pub(crate) struct Lock {
+    path: PathBuf,
+}
+
+impl Lock {
+    pub fn acquire(location: &Path) -> crate::Result<Self> {
+        let path = location.join(LOCK);
+        let mut file = match File::options().write(true).create_new(true).open(&path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                return Err(LedgerError::Locked(path));
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let lock = Self { path };
+        writeln!(file, "{}", process::id())?;
+        Ok(lock)
+    }
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        let _ = remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use super::*;
+
+    #[test]
+    fn lock_is_exclusive() -> crate::Result<()> {
+        let dir = env::temp_dir().join("bok_lock");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir)?;
+        let lock = Lock::acquire(&dir)?;
+        assert!(matches!(
+            Lock::acquire(&dir),
+            Err(LedgerError::Locked(path)) if path == dir.join(LOCK)
+        ));
+        drop(lock);
+        Lock::acquire(&dir)?;
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn replaces_content() -> Result<()> {
+        let dir = env::temp_dir().join("bok_write_atomic");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir)?;
+        let path = dir.join("HEAD");
+        write_atomic(&path, "old")?;
+        write_atomic(&path, "new")?;
+        assert_eq!(fs::read_to_string(&path)?, "new");
+        assert_eq!(fs::read_dir(&dir)?.count(), 1);
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,197 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    Amount, ChartOfAccounts, EntryLine, Error, Result, Side,
+    output::{csv_field, json_opt, json_str},
+};
+
+/// Debit and credit totals of an account.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AccountTotals {
+    pub debit: Amount,
+    pub credit: Amount,
+}
+
+impl AccountTotals {
+    /// The net balance, debit minus credit.
+    pub fn net(&self) -> Amount {
+        self.debit
+            .checked_sub(self.credit)
+            .expect("totals are kept within half the range of an amount")
+    }
+
+    fn add(&mut self, amount: Amount, side: Side) -> Result<()> {
+        let total = match side {
+            Side::Debit => &mut self.debit,
+            Side::Credit => &mut self.credit,
+        };
+        // Keeping both totals non-negative and within i64::MAX / 2 makes `net` infallible.
+        *total = total
+            .checked_add(amount)
+            .filter(|t| t.minor() <= i64::MAX / 2)
+            .ok_or(Error::Overflow)?;
+        Ok(())
+    }
+}
+
+/// Debit and credit totals per account, i.e. a trial balance (råbalans).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrialBalance {
+    accounts: BTreeMap<String, AccountTotals>,
+}
+
+impl TrialBalance {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a line to the totals of its account.
+    pub fn add_line(&mut self, line: &EntryLine) -> Result<()> {
+        self.accounts
+            .entry(line.account.clone())
+            .or_default()
+            .add(line.amount, line.side)
+    }
+
+    /// Totals per account, ordered by account number.
+    pub fn accounts(&self) -> impl Iterator<Item = (&str, &AccountTotals)> {
+        self.accounts.iter().map(|(a, t)| (a.as_str(), t))
+    }
+
+    pub fn get(&self, account: &str) -> Option<&AccountTotals> {
+        self.accounts.get(account)
+    }
+
+    /// Grand totals over all accounts.
+    pub fn totals(&self) -> Result<AccountTotals> {
+        let mut totals = AccountTotals::default();
+        for account in self.accounts.values() {
+            totals.add(account.debit, Side::Debit)?;
+            totals.add(account.credit, Side::Credit)?;
+        }
+        Ok(totals)
+    }
+
+    /// CSV rendering with a row per account and a last row of grand totals.
+    pub fn to_csv(&self, chart: &ChartOfAccounts) -> Result<String> {
+        let row = |account: &str, name: &str, totals: &AccountTotals| {
+            format!(
+                "{},{},{},{},{}\n",
+                csv_field(account),
+                csv_field(name),
+                totals.debit,
+                totals.credit,
+                totals.net()
+            )
+        };
+        let mut result = "account,name,debit,credit,net\n".to_string();
+        for (account, totals) in self.accounts() {
+            result += &row(account, chart.name(account).unwrap_or(""), totals);
+        }
+        result += &row("", "total", &self.totals()?);
+        Ok(result)
+    }
+
+    /// JSON rendering, amounts are decimal strings.
+    pub fn to_json(&self, chart: &ChartOfAccounts) -> Result<String> {
+        let totals = |totals: &AccountTotals| {
+            format!(
+                "\"debit\":{},\"credit\":{},\"net\":{}",
+                json_str(&totals.debit.to_string()),
+                json_str(&totals.credit.to_string()),
+                json_str(&totals.net().to_string())
+            )
+        };
+        let accounts: Vec<String> = self
+            .accounts()
+            .map(|(account, t)| {
+                format!(
+                    "{{\"account\":{},\"name\":{},{}}}",
+                    json_str(account),
+                    json_opt(chart.name(account).map(str::to_string)),
+                    totals(t)
+                )
+            })
+            .collect();
+        Ok(format!(
+            "{{\"accounts\":[{}],\"total\":{{{}}}}}\n",
+            accounts.join(","),
+            totals(&self.totals()?)
+        ))
+    }
+
+    /// Checks that the grand total of debits equals the grand total of credits.
+    pub fn check(&self) -> Result<AccountTotals> {
+        let totals = self.totals()?;
+        if totals.debit != totals.credit {
+            return Err(Error::UnbalancedTotals {
+                debit: totals.debit,
+                credit: totals.credit,
+            });
+        }
+        Ok(totals)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(account: &str, amount: i64, side: Side) -> EntryLine {
+        EntryLine::new(account, Amount::from_minor(amount), side, None)
+    }
+
+    #[test]
+    fn sums_per_account() -> Result<()> {
+        let mut balance = TrialBalance::new();
+        for l in [
+            line("5410", 100, Side::Debit),
+            line("1930", 100, Side::Credit),
+            line("1930", 40, Side::Debit),
+            line("3001", 40, Side::Credit),
+        ] {
+            balance.add_line(&l)?;
+        }
+        assert_eq!(balance.get("1930").unwrap().net(), Amount::from_minor(-60));
+        assert_eq!(balance.check()?.debit, Amount::from_minor(140));
+        Ok(())
+    }
+
+    #[test]
+    fn renders_csv_and_json() -> Result<()> {
+        let mut balance = TrialBalance::new();
+        balance.add_line(&line("5410", 100, Side::Debit))?;
+        balance.add_line(&line("1930", 100, Side::Credit))?;
+        let mut chart = ChartOfAccounts::new();
+        chart.insert(crate::Account {
+            number: "1930".to_string(),
+            name: "Företagskonto, bank".to_string(),
+            class: crate::AccountClass::Asset,
+            vat_code: None,
+            active: true,
+        });
+        assert_eq!(
+            balance.to_csv(&chart)?,
+            "account,name,debit,credit,net\n\
+             1930,\"Företagskonto, bank\",0.00,1.00,-1.00\n\
+             5410,,1.00,0.00,1.00\n\
+             ,total,1.00,1.00,0.00\n"
+        );
+        assert_eq!(
+            balance.to_json(&chart)?,
+            "{\"accounts\":[\
+             {\"account\":\"1930\",\"name\":\"Företagskonto, bank\",\"debit\":\"0.00\",\"credit\":\"1.00\",\"net\":\"-1.00\"},\
+             {\"account\":\"5410\",\"name\":null,\"debit\":\"1.00\",\"credit\":\"0.00\",\"net\":\"1.00\"}],\
+             \"total\":{\"debit\":\"1.00\",\"credit\":\"1.00\",\"net\":\"0.00\"}}\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn unbalanced_totals_are_reported() -> Result<()> {
+        let mut balance = TrialBalance::new();
+        balance.add_line(&line("5410", 100, Side::Debit))?;
+        assert!(balance.check().is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,340 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+use hex::ToHex;
+use sha2::{Digest, Sha256};
+
+use crate::{Error, Result};
+
+/// The class of an account, deciding where it ends up in the reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AccountClass {
+    Asset,
+    Liability,
+    Equity,
+    Income,
+    Expense,
+}
+
+impl AccountClass {
+    /// Guesses the class of an account from its number using the BAS ranges.
+    ///
+    /// 1xxx are assets, 20xx equity, 21xx-29xx liabilities, 3xxx income, 4xxx-7xxx expenses
+    /// and 8xxx are financial items where 80xx-83xx are income and the rest expenses.
+    pub fn from_bas(number: &str) -> Option<Self> {
+        let prefix: u16 = number.get(..2)?.parse().ok()?;
+        match prefix {
+            10..=19 => Some(AccountClass::Asset),
+            20 => Some(AccountClass::Equity),
+            21..=29 => Some(AccountClass::Liability),
+            30..=39 => Some(AccountClass::Income),
+            40..=79 => Some(AccountClass::Expense),
+            80..=83 => Some(AccountClass::Income),
+            84..=89 => Some(AccountClass::Expense),
+            _ => None,
+        }
+    }
+}
+
+impl Display for AccountClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            AccountClass::Asset => "asset",
+            AccountClass::Liability => "liability",
+            AccountClass::Equity => "equity",
+            AccountClass::Income => "income",
+            AccountClass::Expense => "expense",
+        })
+    }
+}
+
+impl FromStr for AccountClass {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "asset" => Ok(AccountClass::Asset),
+            "liability" => Ok(AccountClass::Liability),
+            "equity" => Ok(AccountClass::Equity),
+            "income" => Ok(AccountClass::Income),
+            "expense" => Ok(AccountClass::Expense),
+            _ => Err(Error::InvalidData(format!("Unknown account class: {}", s))),
+        }
+    }
+}
+
+/// A single account in the chart of accounts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Account {
+    pub number: String,
+    pub name: String,
+    pub class: AccountClass,
+    pub vat_code: Option<String>,
+    pub active: bool,
+}
+
+/// The chart of accounts (kontoplan) of a ledger, ordered by account number.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChartOfAccounts {
+    accounts: BTreeMap<String, Account>,
+}
+
+impl ChartOfAccounts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.accounts.len()
+    }
+
+    pub fn get(&self, number: &str) -> Option<&Account> {
+        self.accounts.get(number)
+    }
+
+    pub fn get_mut(&mut self, number: &str) -> Option<&mut Account> {
+        self.accounts.get_mut(number)
+    }
+
+    /// The name of an account, if it's in the chart.
+    pub fn name(&self, number: &str) -> Option<&str> {
+        self.get(number).map(|a| a.name.as_str())
+    }
+
+    /// Adds or replaces an account.
+    pub fn insert(&mut self, account: Account) -> Option<Account> {
+        self.accounts.insert(account.number.clone(), account)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Account> {
+        self.accounts.values()
+    }
+
+    /// Checks that an account can be used in a new entry.
+    ///
+    /// An empty chart accepts every account, so books can be kept before a chart is imported.
+    pub fn check_usable(&self, number: &str) -> Result<()> {
+        if self.is_empty() {
+            return Ok(());
+        }
+        match self.get(number) {
+            None => Err(Error::UnknownAccount(number.to_string())),
+            Some(account) if !account.active => Err(Error::InactiveAccount {
+                number: number.to_string(),
+                name: account.name.clone(),
+            }),
+            Some(_) => Ok(()),
+        }
+    }
+
+    /// Imports a BAS kontoplan exported as CSV from the Excel sheet at bas.se.
+    ///
+    /// Fields can be separated by `;`, `,` or tabs. If a header row names the columns
+    /// (`Konto`/`Account`, `Benämning`/`Namn`/`Name` and optionally `Moms`/`VAT`) those
+    /// columns are used, otherwise every four digit number followed by a name is picked up,
+    /// which handles the multi-column layout of the official sheet.
+    pub fn from_bas_csv(content: &str) -> Result<Self> {
+        let content = content.trim_start_matches('\u{feff}');
+        let delimiter = [';', '\t', ',']
+            .into_iter()
+            .max_by_key(|d| content.lines().take(20).filter(|l| l.contains(*d)).count())
+            .unwrap_or(';');
+        let rows: Vec<Vec<String>> = content
+            .lines()
+            .map(|l| split_csv_line(l, delimiter))
+            .collect();
+
+        let mut chart = ChartOfAccounts::new();
+        match rows
+            .iter()
+            .enumerate()
+            .find_map(|(i, r)| header(r).map(|h| (i, h)))
+        {
+            Some((header_row, (number_col, name_col, vat_col))) => {
+                for row in &rows[header_row + 1..] {
+                    let field = |c: usize| row.get(c).map(|f| f.trim()).unwrap_or("");
+                    let vat = vat_col.map(field).filter(|v| !v.is_empty());
+                    chart.insert_bas(field(number_col), field(name_col), vat);
+                }
+            }
+            None => {
+                for row in &rows {
+                    for pair in row.windows(2) {
+                        chart.insert_bas(pair[0].trim(), pair[1].trim(), None);
+                    }
+                }
+            }
+        }
+        if chart.is_empty() {
+            return Err(Error::InvalidData(
+                "No accounts found in kontoplan".to_string(),
+            ));
+        }
+        Ok(chart)
+    }
+
+    fn insert_bas(&mut self, number: &str, name: &str, vat_code: Option<&str>) {
+        let is_account = number.len() == 4 && number.chars().all(|c| c.is_ascii_digit());
+        if !is_account || name.is_empty() {
+            return;
+        }
+        if let Some(class) = AccountClass::from_bas(number) {
+            self.insert(Account {
+                number: number.to_string(),
+                name: name.to_string(),
+                class,
+                vat_code: vat_code.map(str::to_string),
+                active: true,
+            });
+        }
+    }
+
+    /// Serializes the chart as text, returning its hash and content.
+    ///
+    /// The first line links to the previous version of the chart, if any, followed by one
+    /// tab separated line per account:
+    /// ```ignore
+    /// previous <hash>
+    /// <number>\t<name>\t<class>\t<vat code>\t<1 if active else 0>
+    /// ```
+    /// Fields containing tabs or line breaks would corrupt the file and are rejected.
+    pub(crate) fn serialize(&self, previous: Option<&str>) -> Result<(String, String)> {
+        let mut out = String::new();
+        if let Some(previous) = previous {
+            out.push_str(&format!("previous {}\n", previous));
+        }
+        for a in self.iter() {
+            let fields = [&a.number, &a.name, a.vat_code.as_deref().unwrap_or("")];
+            if fields.iter().any(|f| f.contains(['\t', '\n', '\r'])) {
+                return Err(Error::InvalidInput(format!(
+                    "Account {:?} {:?} can't contain tabs or line breaks",
+                    a.number, a.name
+                )));
+            }
+            out.push_str(&format!(
+                "{}\t{}\t{}\t{}\t{}\n",
+                a.number,
+                a.name,
+                a.class,
+                a.vat_code.as_deref().unwrap_or(""),
+                if a.active { 1 } else { 0 }
+            ));
+        }
+        let hash = Sha256::digest(out.as_bytes()).encode_hex();
+        Ok((hash, out))
+    }
+
+    pub(crate) fn deserialize(content: &str) -> Result<Self> {
+        let bad =
+            |line: &str| Error::InvalidData(format!("Invalid chart of accounts line: {}", line));
+        let mut chart = ChartOfAccounts::new();
+        for line in content.lines().filter(|l| !l.starts_with("previous ")) {
+            let fields: Vec<&str> = line.split('\t').collect();
+            let [number, name, class, vat_code, active] = fields[..] else {
+                return Err(bad(line));
+            };
+            chart.insert(Account {
+                number: number.to_string(),
+                name: name.to_string(),
+                class: class.parse()?,
+                vat_code: Some(vat_code).filter(|v| !v.is_empty()).map(str::to_string),
+                active: match active {
+                    "1" => true,
+                    "0" => false,
+                    _ => return Err(bad(line)),
+                },
+            });
+        }
+        Ok(chart)
+    }
+}
+
+/// Finds the account, name and VAT columns of a header row.
+fn header(row: &[String]) -> Option<(usize, usize, Option<usize>)> {
+    let find = |names: &[&str]| {
+        row.iter()
+            .position(|f| names.contains(&f.trim().to_lowercase().as_str()))
+    };
+    let number = find(&["konto", "kontonummer", "account", "number"])?;
+    let name = find(&["benämning", "kontonamn", "namn", "name"])?;
+    Some((number, name, find(&["moms", "momskod", "vat", "vat code"])))
+}
+
+/// Splits a CSV line, honouring double quoted fields.
+fn split_csv_line(line: &str, delimiter: char) -> Vec<String> {
+    let mut fields = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' => quoted = !quoted,
+            c if c == delimiter && !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn imports_bas_sheet_layout() -> Result<()> {
+        let csv = "Kontoplan BAS 2025;;;;\n\
+                   ;1930;Företagskonto / checkkonto / affärskonto;;\n\
+                   ;2640;Ingående moms;2641;\"Debiterad ingående moms\"\n\
+                   ;5410;Förbrukningsinventarier;;\n";
+        let chart = ChartOfAccounts::from_bas_csv(csv)?;
+        assert_eq!(chart.len(), 4);
+        assert_eq!(chart.name("2641"), Some("Debiterad ingående moms"));
+        assert_eq!(chart.get("2641").unwrap().class, AccountClass::Liability);
+        assert_eq!(chart.get("5410").unwrap().class, AccountClass::Expense);
+        Ok(())
+    }
+
+    #[test]
+    fn imports_csv_with_header() -> Result<()> {
+        let csv = "Konto,Benämning,Moms\n3001,\"Försäljning, 25 %\",MP1\n1930,Bank,\n";
+        let chart = ChartOfAccounts::from_bas_csv(csv)?;
+        assert_eq!(chart.name("3001"), Some("Försäljning, 25 %"));
+        assert_eq!(chart.get("3001").unwrap().vat_code.as_deref(), Some("MP1"));
+        assert_eq!(chart.get("1930").unwrap().vat_code, None);
+        Ok(())
+    }
+
+    #[test]
+    fn serialization_round_trips() -> Result<()> {
+        let mut chart = ChartOfAccounts::from_bas_csv("1930;Bank\n2099;Årets resultat\n")?;
+        chart.get_mut("1930").unwrap().active = false;
+        let (_, content) = chart.serialize(Some("abc"))?;
+        assert_eq!(ChartOfAccounts::deserialize(&content)?, chart);
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_tabs_and_line_breaks() -> Result<()> {
+        let mut chart = ChartOfAccounts::from_bas_csv("Konto;Namn\n1930;\"Bank\tkonto\"\n")?;
+        assert_eq!(chart.name("1930"), Some("Bank\tkonto"));
+        assert!(matches!(chart.serialize(None), Err(Error::InvalidInput(_))));
+        chart.get_mut("1930").unwrap().name = "Bank\nkonto".to_string();
+        assert!(matches!(chart.serialize(None), Err(Error::InvalidInput(_))));
+        chart.get_mut("1930").unwrap().name = "Bankkonto".to_string();
+        chart.get_mut("1930").unwrap().vat_code = Some("\r".to_string());
+        assert!(matches!(chart.serialize(None), Err(Error::InvalidInput(_))));
+        Ok(())
+    }
+}
@@ -1,13 +1,18 @@
 use std::fs::File;
-use std::io::Result;
 use std::path::Path;
 
-use chrono::{DateTime, NaiveDate, Timelike, Utc};
+use chrono::{DateTime, Datelike, NaiveDate, Timelike, Utc};
 
-use super::EntryLine;
+use super::{EntryLine, check_balance};
+use crate::{Error, Result};
 
 /// Entry in the General Ledger.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(::serde::Serialize, ::serde::Deserialize),
+    serde(tag = "type", rename_all = "snake_case")
+)]
 pub enum Entry {
     Entry {
         timestamp: DateTime<Utc>,
@@ -19,31 +24,70 @@ pub enum Entry {
     },
     Origin {
         timestamp: DateTime<Utc>,
-        year: u64,
+        /// First day of the fiscal year.
+        start: NaiveDate,
+        /// Last day of the fiscal year.
+        end: NaiveDate,
+        /// Hash of the last entry of the previous fiscal year, none for the first year.
+        #[cfg_attr(feature = "serde", serde(default))]
+        previous_year: Option<String>,
     },
 }
 
 impl Entry {
+    /// Name of the entry holding the opening balances (ingående balanser) of a fiscal year.
+    pub const OPENING_BALANCE: &'static str = "IB";
+
+    /// Start of the description of an entry correcting another, followed by its hash.
+    pub const CORRECTION_OF: &'static str = "Correction of ";
+
     /// Constructor for the Entry::Entry variant
+    ///
+    /// Fails with [`Error::Unbalanced`] if the lines don't form a balanced double-entry
+    /// voucher.
     pub fn new(
         date: NaiveDate,
         name: &str,
         description: &str,
         lines: Vec<EntryLine>,
         previous_entry: &str,
-    ) -> Self {
-        Entry::Entry {
+    ) -> Result<Self> {
+        check_balance(&lines)?;
+        Ok(Entry::Entry {
             timestamp: chrono::Utc::now().with_nanosecond(0).unwrap(),
             event_date: date,
             name: name.to_string(),
             description: description.to_string(),
             lines,
             previous_entry: previous_entry.to_string(),
-        }
+        })
+    }
+
+    /// Whether this is the entry holding the opening balances of the fiscal year.
+    pub fn is_opening_balance(&self) -> bool {
+        matches!(self, Entry::Entry { name, .. } if name == Self::OPENING_BALANCE)
     }
 
+    /// The hash of the entry this entry corrects, if it's a correction.
+    pub fn corrects(&self) -> Option<&str> {
+        let Entry::Entry { description, .. } = self else {
+            return None;
+        };
+        let hash = description.strip_prefix(Self::CORRECTION_OF)?.get(..64)?;
+        hash.chars().all(|c| c.is_ascii_hexdigit()).then_some(hash)
+    }
+
+    /// Reads an entry from a file, named by its hash in error messages.
     pub fn from_file(path: &Path) -> Result<Self> {
         let mut file = File::open(path)?;
-        Self::deserialize(&mut file)
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        Self::deserialize(&mut file).map_err(Error::corrupt(&name))
     }
 }
+
+/// The year if the fiscal year is a calendar year, the only kind the original Origin could hold.
+pub(super) fn calendar_year(start: NaiveDate, end: NaiveDate) -> Option<i32> {
+    let year = start.year();
+    (year >= 0 && start.ordinal() == 1 && end == NaiveDate::from_ymd_opt(year, 12, 31)?)
+        .then_some(year)
+}
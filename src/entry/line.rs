@@ -1,24 +1,41 @@
+use crate::Amount;
 use crate::read::read;
 use std::io::{Read, Result, Write};
 
 /// Journal EntryLine used for accounting
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 pub struct EntryLine {
     pub account: String,
-    pub amount: usize, // Amount in smallest currency unit
-    pub side: Side,    // true for debit, false for credit
+    pub amount: Amount,
+    pub side: Side, // true for debit, false for credit
     pub description: Option<String>,
 }
 
 #[derive(Debug, PartialEq, Clone, Copy, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(::serde::Serialize, ::serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
 pub enum Side {
     Debit,
     Credit,
 }
 
+impl Side {
+    /// The other side, debit for credit and credit for debit.
+    pub fn opposite(self) -> Side {
+        match self {
+            Side::Debit => Side::Credit,
+            Side::Credit => Side::Debit,
+        }
+    }
+}
+
 impl EntryLine {
     /// Simple constructor for EntryLine
-    pub fn new(account: &str, amount: usize, side: Side, description: Option<String>) -> Self {
+    pub fn new(account: &str, amount: Amount, side: Side, description: Option<String>) -> Self {
         EntryLine {
             account: account.to_string(),
             amount,
@@ -44,8 +61,14 @@ impl EntryLine {
         // Write account length (4 bytes)
         output.write_all(&(self.account.len() as u32).to_le_bytes())?;
 
-        // Write amount (8 bytes)
-        output.write_all(&(self.amount as u64).to_le_bytes())?;
+        // Write amount (8 bytes, in smallest currency unit)
+        let amount = u64::try_from(self.amount.minor()).map_err(|_| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Negative amount can't be stored: {}", self.amount),
+            )
+        })?;
+        output.write_all(&amount.to_le_bytes())?;
 
         // Write side (1 byte: 0x00 = credit, 0x01 = debit)
         let side_byte = match self.side {
@@ -77,7 +100,13 @@ impl EntryLine {
     pub(crate) fn deserialize<R: Read>(reader: &mut R) -> std::io::Result<Self> {
         let buffer: [u8; 8] = [0; 8];
         read!(account_len(u32) as usize from reader using buffer);
-        read!(amount(u64) as usize from reader using buffer);
+        read!(amount(u64) from reader using buffer);
+        let amount = i64::try_from(amount).map(Amount::from_minor).map_err(|_| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Amount out of range: {}", amount),
+            )
+        })?;
         read!(side_byte(u8) from reader using buffer);
         let side = match side_byte {
             0x00 => Side::Credit,
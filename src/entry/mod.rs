@@ -1,8 +1,12 @@
 mod entry_struct;
 mod line;
+mod output;
 mod serde;
 mod show;
+mod validate;
 
 // Exposed Entries
 pub use entry_struct::Entry;
+use entry_struct::calendar_year;
 pub use line::{EntryLine, Side};
+pub use validate::{BalanceError, check_balance};
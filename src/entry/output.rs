@@ -0,0 +1,205 @@
+use std::io::empty;
+
+use chrono::SecondsFormat;
+
+use super::{Entry, EntryLine, Side};
+use crate::{
+    Result,
+    output::{csv_field, json_opt, json_str},
+};
+
+impl Side {
+    /// The side as written in JSON and CSV, `debit` or `credit`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Side::Debit => "debit",
+            Side::Credit => "credit",
+        }
+    }
+}
+
+impl EntryLine {
+    fn to_json(&self) -> String {
+        format!(
+            "{{\"account\":{},\"amount\":{},\"side\":{},\"description\":{}}}",
+            json_str(&self.account),
+            json_str(&self.amount.to_string()),
+            json_str(self.side.as_str()),
+            json_opt(self.description.clone())
+        )
+    }
+}
+
+impl Entry {
+    /// Header of the rows written by [`Entry::to_csv`].
+    pub const CSV_HEADER: &'static str =
+        "hash,timestamp,event_date,name,description,account,side,amount,line_description\n";
+
+    /// The entry as a JSON object with its full hash, one line without a trailing newline.
+    ///
+    /// Timestamps are RFC 3339 in UTC, dates `YYYY-MM-DD` and amounts decimal strings. With
+    /// the `serde` feature, serializing the entry gives the same object without the hash.
+    /// ```ignore
+    /// {"hash":"3cc0...","type":"origin","timestamp":"2025-01-01T09:00:00Z",
+    ///  "start":"2025-01-01","end":"2025-12-31","previous_year":null}
+    /// {"hash":"9f7c...","type":"entry","timestamp":"2025-02-01T09:00:00Z",
+    ///  "event_date":"2025-02-01","name":"A1","description":"Pennor",
+    ///  "lines":[{"account":"5410","amount":"100.00","side":"debit","description":null},
+    ///           {"account":"1930","amount":"100.00","side":"credit","description":null}],
+    ///  "previous_entry":"3cc0..."}
+    /// ```
+    pub fn to_json(&self) -> Result<String> {
+        let hash = self.serialize(empty())?;
+        Ok(match self {
+            Entry::Origin {
+                timestamp,
+                start,
+                end,
+                previous_year,
+            } => format!(
+                "{{\"hash\":{},\"type\":\"origin\",\"timestamp\":{},\"start\":{},\"end\":{},\
+                 \"previous_year\":{}}}",
+                json_str(&hash),
+                json_str(&timestamp.to_rfc3339_opts(SecondsFormat::AutoSi, true)),
+                json_str(&start.to_string()),
+                json_str(&end.to_string()),
+                json_opt(previous_year.clone())
+            ),
+            Entry::Entry {
+                timestamp,
+                event_date,
+                name,
+                description,
+                lines,
+                previous_entry,
+            } => {
+                let lines: Vec<String> = lines.iter().map(EntryLine::to_json).collect();
+                format!(
+                    "{{\"hash\":{},\"type\":\"entry\",\"timestamp\":{},\"event_date\":{},\
+                     \"name\":{},\"description\":{},\"lines\":[{}],\"previous_entry\":{}}}",
+                    json_str(&hash),
+                    json_str(&timestamp.to_rfc3339_opts(SecondsFormat::AutoSi, true)),
+                    json_str(&event_date.to_string()),
+                    json_str(name),
+                    json_str(description),
+                    lines.join(","),
+                    json_str(previous_entry)
+                )
+            }
+        })
+    }
+
+    /// The entry as CSV rows under [`Entry::CSV_HEADER`], one per line of the entry with
+    /// the fields of the entry repeated. The Origin has no lines and gives no rows.
+    pub fn to_csv(&self) -> Result<String> {
+        let Entry::Entry {
+            timestamp,
+            event_date,
+            name,
+            description,
+            lines,
+            ..
+        } = self
+        else {
+            return Ok(String::new());
+        };
+        let hash = self.serialize(empty())?;
+        let timestamp = timestamp.to_rfc3339_opts(SecondsFormat::AutoSi, true);
+        let mut result = String::new();
+        for line in lines {
+            result += &format!(
+                "{},{},{},{},{},{},{},{},{}\n",
+                hash,
+                timestamp,
+                event_date,
+                csv_field(name),
+                csv_field(description),
+                csv_field(&line.account),
+                line.side.as_str(),
+                line.amount,
+                csv_field(line.description.as_deref().unwrap_or(""))
+            );
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, NaiveDate};
+
+    use super::*;
+    use crate::Amount;
+
+    fn entry() -> Entry {
+        Entry::Entry {
+            timestamp: DateTime::from_timestamp(1_738_400_400, 0).unwrap(),
+            event_date: NaiveDate::from_ymd_opt(2025, 2, 1).unwrap(),
+            name: "A1".to_string(),
+            description: "Pennor, \"blå\"".to_string(),
+            lines: vec![
+                EntryLine::new(
+                    "5410",
+                    Amount::from_minor(10050),
+                    Side::Debit,
+                    Some("Kontor".to_string()),
+                ),
+                EntryLine::new("1930", Amount::from_minor(10050), Side::Credit, None),
+            ],
+            previous_entry: "0".repeat(64),
+        }
+    }
+
+    #[test]
+    fn renders_json_and_csv() -> Result<()> {
+        let entry = entry();
+        let hash = entry.serialize(empty())?;
+        assert_eq!(
+            entry.to_json()?,
+            format!(
+                "{{\"hash\":\"{}\",\"type\":\"entry\",\"timestamp\":\"2025-02-01T09:00:00Z\",\
+                 \"event_date\":\"2025-02-01\",\"name\":\"A1\",\"description\":\"Pennor, \\\"blå\\\"\",\
+                 \"lines\":[{{\"account\":\"5410\",\"amount\":\"100.50\",\"side\":\"debit\",\
+                 \"description\":\"Kontor\"}},{{\"account\":\"1930\",\"amount\":\"100.50\",\
+                 \"side\":\"credit\",\"description\":null}}],\"previous_entry\":\"{}\"}}",
+                hash,
+                "0".repeat(64)
+            )
+        );
+        assert_eq!(
+            entry.to_csv()?,
+            format!(
+                "{h},2025-02-01T09:00:00Z,2025-02-01,A1,\"Pennor, \"\"blå\"\"\",5410,debit,100.50,Kontor\n\
+                 {h},2025-02-01T09:00:00Z,2025-02-01,A1,\"Pennor, \"\"blå\"\"\",1930,credit,100.50,\n",
+                h = hash
+            )
+        );
+        let origin = Entry::Origin {
+            timestamp: DateTime::from_timestamp(0, 0).unwrap(),
+            start: NaiveDate::from_ymd_opt(2025, 7, 1).unwrap(),
+            end: NaiveDate::from_ymd_opt(2026, 6, 30).unwrap(),
+            previous_year: Some(hash.clone()),
+        };
+        assert!(origin.to_json()?.ends_with(&format!(
+            "\"type\":\"origin\",\"timestamp\":\"1970-01-01T00:00:00Z\",\
+             \"start\":\"2025-07-01\",\"end\":\"2026-06-30\",\"previous_year\":\"{}\"}}",
+            hash
+        )));
+        assert_eq!(origin.to_csv()?, "");
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_matches_json() -> Result<()> {
+        let entry = entry();
+        let json = serde_json::to_string(&entry).unwrap();
+        let hash = entry.serialize(empty())?;
+        assert_eq!(
+            entry.to_json()?,
+            format!("{{\"hash\":\"{}\",{}", hash, &json[1..])
+        );
+        assert_eq!(serde_json::from_str::<Entry>(&json).unwrap(), entry);
+        Ok(())
+    }
+}
@@ -6,7 +6,7 @@ use flate2::read::GzDecoder;
 use hex::ToHex;
 use sha2::{Digest, Sha256};
 
-use super::{Entry, EntryLine};
+use super::{Entry, EntryLine, calendar_year};
 use crate::read::read;
 use crate::tee_writer::TeeWriter;
 use flate2::write::GzEncoder;
@@ -16,11 +16,27 @@ impl Entry {
     ///
     /// Returns the hash as the result if successful
     ///
-    /// Origin Variant (0x00):
+    /// Origin Variant (0x00), the first fiscal year, a calendar year:
     /// +--------+--------------------------+-------------------------+
     /// | 0x00   | year (8 bytes)           | timestamp (8 bytes)     |
     /// +--------+--------------------------+-------------------------+
     ///
+    /// Origin Variant (0x02), a calendar year following another fiscal year:
+    /// +--------+--------------------------+-------------------------+
+    /// | 0x02   | year (8 bytes)           | timestamp (8 bytes)     |
+    /// +--------+--------------------------+-------------------------+
+    /// | previous_year_id (64 B)                                     |
+    /// +-------------------------------------------------------------+
+    ///
+    /// Origin Variant version 2 (0x03, or 0x04 followed by previous_year_id), any other
+    /// fiscal year, i.e. 2025-07-01 to 2026-06-30:
+    /// +--------+-------------+-----------+-----------------+
+    /// | 0x03   | start (4 B) | end (4 B) | timestamp (8 B) |
+    /// +--------+-------------+-----------+-----------------+
+    ///
+    /// Calendar years are always written in the original layout, so the hashes of Origins
+    /// written before fiscal years had dates stay the same.
+    ///
     /// Entry Variant (0x01):
     /// +--------+------------+-----------------+----------------+----------------+
     /// | 0x01   | date (4 B) | timestamp (8 B) | name_len (4 B) | desc_len (4 B) |
@@ -41,14 +57,34 @@ impl Entry {
         let mut output = TeeWriter::new(zipper, Sha256::new());
 
         match self {
-            Entry::Origin { timestamp, year } => {
-                // Write discriminant for Origin
-                output.write_all(&[0x00])?;
-                // Write year as 8-byte little-endian
-                output.write_all(&year.to_le_bytes())?;
+            Entry::Origin {
+                timestamp,
+                start,
+                end,
+                previous_year,
+            } => {
+                let following = previous_year.is_some();
+                match calendar_year(*start, *end) {
+                    Some(year) => {
+                        // Write discriminant for Origin, a first year keeps the original layout
+                        output.write_all(&[if following { 0x02 } else { 0x00 }])?;
+                        // Write year as 8-byte little-endian
+                        output.write_all(&(year as u64).to_le_bytes())?;
+                    }
+                    None => {
+                        output.write_all(&[if following { 0x04 } else { 0x03 }])?;
+                        // Write start and end as days since year 0, 4 bytes, little endian
+                        output.write_all(&start.num_days_from_ce().to_le_bytes())?;
+                        output.write_all(&end.num_days_from_ce().to_le_bytes())?;
+                    }
+                }
                 // Write timestamp as 8-byte little-endian
                 let epoch_secs = timestamp.timestamp();
                 output.write_all(&epoch_secs.to_le_bytes())?;
+                // Write previous_year_id (64 bytes) at the end
+                if let Some(previous_year) = previous_year {
+                    output.write_all(previous_year.as_bytes())?;
+                }
             }
 
             Entry::Entry {
@@ -116,14 +152,43 @@ impl Entry {
         // Read discriminant
         read!(discriminant(u8) from reader using buffer);
         match discriminant {
-            0x00 => {
-                // Origin variant: need 8 bytes for year and 8 for timestamp
-                read!(year(u64) from reader using buffer);
+            0x00 | 0x02 | 0x03 | 0x04 => {
+                let invalid = |msg: &str| std::io::Error::new(std::io::ErrorKind::InvalidData, msg);
+                let (start, end) = if discriminant <= 0x02 {
+                    // Original Origin variant: a calendar year in 8 bytes
+                    read!(year(u64) from reader using buffer);
+                    let date = |m, d| {
+                        i32::try_from(year)
+                            .ok()
+                            .and_then(|year| NaiveDate::from_ymd_opt(year, m, d))
+                    };
+                    date(1, 1)
+                        .zip(date(12, 31))
+                        .ok_or_else(|| invalid("Origin year out of range"))?
+                } else {
+                    read!(start_days(i32) from reader using buffer);
+                    read!(end_days(i32) from reader using buffer);
+                    let date = |days| {
+                        NaiveDate::from_num_days_from_ce_opt(days)
+                            .ok_or_else(|| invalid("Invalid fiscal year date"))
+                    };
+                    (date(start_days)?, date(end_days)?)
+                };
                 read!(epoch_secs(i64) from reader using buffer);
-                let timestamp = DateTime::from_timestamp(epoch_secs, 0).ok_or_else(|| {
-                    std::io::Error::new(std::io::ErrorKind::InvalidData, "Invalid timestamp")
-                })?;
-                Ok(Entry::Origin { year, timestamp })
+                let timestamp = DateTime::from_timestamp(epoch_secs, 0)
+                    .ok_or_else(|| invalid("Invalid timestamp"))?;
+                let previous_year = if matches!(discriminant, 0x02 | 0x04) {
+                    read!(previous_year(64) as String from reader);
+                    Some(previous_year)
+                } else {
+                    None
+                };
+                Ok(Entry::Origin {
+                    timestamp,
+                    start,
+                    end,
+                    previous_year,
+                })
             }
             0x01 => {
                 // Read event_date (4 bytes, little endian)
@@ -174,7 +239,7 @@ mod tests {
     use std::{env, fs::File, io::Cursor, ops::Deref};
 
     use super::*;
-    use crate::Side;
+    use crate::{Amount, Side};
     use chrono::{NaiveDate, TimeZone, Utc};
     use quickcheck::{Arbitrary, Gen};
     use quickcheck_macros::quickcheck;
@@ -183,7 +248,7 @@ mod tests {
     impl Arbitrary for EntryLine {
         fn arbitrary(g: &mut Gen) -> Self {
             let account = String::arbitrary(g);
-            let amount = usize::arbitrary(g) % 10_000_000;
+            let amount = Amount::from_minor((u64::arbitrary(g) % 10_000_000) as i64);
             let side = if bool::arbitrary(g) {
                 Side::Debit
             } else {
@@ -237,9 +302,17 @@ mod tests {
     impl Arbitrary for Entry {
         fn arbitrary(g: &mut Gen) -> Self {
             if bool::arbitrary(g) {
+                let start = ArbDateTime::arbitrary(g).date_naive();
                 Entry::Origin {
                     timestamp: *ArbDateTime::arbitrary(g),
-                    year: ArbDateTime::arbitrary(g).year() as u64,
+                    start,
+                    end: start + chrono::Days::new(u64::arbitrary(g) % 549),
+                    previous_year: bool::arbitrary(g).then(|| {
+                        (0..32)
+                            .map(|_| u8::arbitrary(g))
+                            .collect::<Vec<u8>>()
+                            .encode_hex::<String>()
+                    }),
                 }
             } else {
                 Entry::Entry {
@@ -258,6 +331,29 @@ mod tests {
         }
     }
 
+    #[test]
+    fn first_year_origin_keeps_its_hash() -> Result<()> {
+        let timestamp = Utc.with_ymd_and_hms(2025, 1, 1, 9, 0, 0).unwrap();
+        let origin = |previous_year| Entry::Origin {
+            timestamp,
+            start: NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(),
+            end: NaiveDate::from_ymd_opt(2025, 12, 31).unwrap(),
+            previous_year,
+        };
+        let mut bytes = vec![0x00];
+        bytes.extend(2025u64.to_le_bytes());
+        bytes.extend(timestamp.timestamp().to_le_bytes());
+        let hash: String = Sha256::digest(&bytes).encode_hex();
+        assert_eq!(origin(None).serialize(empty())?, hash);
+
+        let next = origin(Some(hash.clone()));
+        let mut buf = Cursor::new(Vec::new());
+        assert_ne!(next.serialize(&mut buf)?, hash);
+        buf.set_position(0);
+        assert_eq!(Entry::deserialize(&mut buf)?, next);
+        Ok(())
+    }
+
     #[quickcheck]
     fn prop_entry_ser_de(entry: Entry) -> Result<bool> {
         let mut buf = Cursor::new(Vec::new());
@@ -315,7 +411,7 @@ mod tests {
     }
 
     #[quickcheck]
-    fn hash_consistent_on_disk(entry: Entry) -> Result<bool> {
+    fn hash_consistent_on_disk(entry: Entry) -> crate::Result<bool> {
         let dir = env::temp_dir();
         let path = dir.join("temp_hash");
         dbg!(&path);
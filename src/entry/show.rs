@@ -1,5 +1,10 @@
-use super::{Entry, Side};
-use std::io::Result;
+use chrono::NaiveDate;
+
+use super::{Entry, Side, calendar_year};
+use crate::{ChartOfAccounts, Period, Result};
+
+/// Longest account label printed by `show`, longer names are cut off.
+const MAX_LABEL_LEN: usize = 40;
 
 impl Entry {
     /// Prints the Entry in a short format, often used in log.
@@ -14,14 +19,28 @@ impl Entry {
     ///   account 2         |     100   # Second description
     /// ```
     pub fn show(&self) -> String {
+        self.show_with_chart(&ChartOfAccounts::new())
+    }
+
+    /// Same as [`Entry::show`] but prints account names from the chart next to the numbers.
+    pub fn show_with_chart(&self, chart: &ChartOfAccounts) -> String {
         match self {
-            Entry::Origin { timestamp, year } => {
-                format!(
+            Entry::Origin {
+                timestamp,
+                start,
+                end,
+                previous_year,
+            } => {
+                let mut result = format!(
                     "({}) {}, Origin of {}\n",
                     self.short_hash().unwrap_or("FAIL".to_string()),
                     timestamp,
-                    year
-                )
+                    fiscal_year(*start, *end)
+                );
+                if let Some(previous_year) = previous_year {
+                    result.push_str(&format!("Following {}\n", previous_year));
+                }
+                result
             }
             Entry::Entry {
                 event_date,
@@ -43,18 +62,37 @@ impl Entry {
                 result.push('\n');
                 result.push('\n');
 
-                result.push_str(&format!("{: >10} {:>10} | {:>10}", "", "debit", "credit"));
+                let labels: Vec<String> = lines
+                    .iter()
+                    .map(|line| match chart.name(&line.account) {
+                        Some(name) => format!("{} {}", line.account, name)
+                            .chars()
+                            .take(MAX_LABEL_LEN)
+                            .collect(),
+                        None => line.account.clone(),
+                    })
+                    .collect();
+                let width = labels.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+                let align_left = width > 10;
+                let width = width.max(10);
+
+                result.push_str(&format!(
+                    "{: >width$} {:>10} | {:>10}",
+                    "", "debit", "credit"
+                ));
                 result.push('\n');
-                for line in lines {
+                for (line, label) in lines.iter().zip(labels) {
                     let (debit, credit) = if line.side == Side::Debit {
                         (line.amount.to_string(), "".to_string())
                     } else {
                         ("".to_string(), line.amount.to_string())
                     };
-                    result.push_str(&format!(
-                        "{: >10} {:>10} | {:>10}",
-                        line.account, debit, credit
-                    ));
+                    let label = if align_left {
+                        format!("{: <width$}", label)
+                    } else {
+                        format!("{: >width$}", label)
+                    };
+                    result.push_str(&format!("{} {:>10} | {:>10}", label, debit, credit));
                     if let Some(description) = &line.description {
                         result.push_str(&format!("# {}", description));
                     }
@@ -70,9 +108,9 @@ impl Entry {
     /// Description is truncated if too long.
     pub fn show_short(&self) -> Result<String> {
         match self {
-            Entry::Origin { year, .. } => Ok(format!(
+            Entry::Origin { start, end, .. } => Ok(format!(
                 "----------------{}---------------({})\n",
-                year,
+                fiscal_year(*start, *end),
                 self.short_hash()?
             )),
             Entry::Entry {
@@ -100,3 +138,11 @@ impl Entry {
         }
     }
 }
+
+/// The fiscal year as its year when it's a calendar year, otherwise as its first and last day.
+fn fiscal_year(start: NaiveDate, end: NaiveDate) -> String {
+    match calendar_year(start, end) {
+        Some(year) => year.to_string(),
+        None => Period::new(Some(start), Some(end)).to_string(),
+    }
+}
@@ -0,0 +1,143 @@
+use std::fmt::{self, Display};
+
+use super::{EntryLine, Side};
+use crate::Amount;
+
+/// Reasons a set of lines can't be recorded as a double-entry voucher.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BalanceError {
+    /// The entry has no lines at all.
+    NoLines,
+    /// Every line is on the same side.
+    OneSided(Side),
+    /// A line has a negative amount, the side decides the sign.
+    NegativeAmount { account: String, amount: Amount },
+    /// The sum of the lines doesn't fit in an amount.
+    Overflow,
+    /// The sum of debits doesn't equal the sum of credits.
+    Unbalanced { debit: Amount, credit: Amount },
+    /// Every line is zero, so the entry moves nothing.
+    ZeroTotal,
+}
+
+impl Display for BalanceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BalanceError::NoLines => write!(f, "entry has no lines"),
+            BalanceError::OneSided(side) => write!(f, "every line of the entry is a {:?}", side),
+            BalanceError::NegativeAmount { account, amount } => {
+                write!(
+                    f,
+                    "line on account {} has negative amount {}",
+                    account, amount
+                )
+            }
+            BalanceError::Overflow => write!(f, "entry total is too large"),
+            BalanceError::Unbalanced { debit, credit } => write!(
+                f,
+                "entry is unbalanced: debit {} != credit {} (difference {})",
+                debit,
+                credit,
+                Amount::from_minor(debit.minor().abs_diff(credit.minor()) as i64)
+            ),
+            BalanceError::ZeroTotal => write!(f, "every line of the entry is zero"),
+        }
+    }
+}
+
+impl std::error::Error for BalanceError {}
+
+/// Checks that the lines make up a balanced double-entry voucher.
+pub fn check_balance(lines: &[EntryLine]) -> Result<(), BalanceError> {
+    let first = lines.first().ok_or(BalanceError::NoLines)?;
+    if lines.iter().all(|l| l.side == first.side) {
+        return Err(BalanceError::OneSided(first.side));
+    }
+    let (mut debit, mut credit) = (Amount::ZERO, Amount::ZERO);
+    for line in lines {
+        if line.amount.is_negative() {
+            return Err(BalanceError::NegativeAmount {
+                account: line.account.clone(),
+                amount: line.amount,
+            });
+        }
+        let total = match line.side {
+            Side::Debit => &mut debit,
+            Side::Credit => &mut credit,
+        };
+        *total = total
+            .checked_add(line.amount)
+            .ok_or(BalanceError::Overflow)?;
+    }
+    if debit != credit {
+        return Err(BalanceError::Unbalanced { debit, credit });
+    }
+    if debit.is_zero() {
+        return Err(BalanceError::ZeroTotal);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::{Entry, Error};
+
+    fn line(amount: i64, side: Side) -> EntryLine {
+        EntryLine::new("1930", Amount::from_minor(amount), side, None)
+    }
+
+    #[test]
+    fn balanced_entry_is_accepted() {
+        let lines = vec![
+            line(100, Side::Debit),
+            line(25, Side::Debit),
+            line(125, Side::Credit),
+        ];
+        assert_eq!(check_balance(&lines), Ok(()));
+    }
+
+    #[test]
+    fn empty_entry_is_rejected() {
+        assert_eq!(check_balance(&[]), Err(BalanceError::NoLines));
+    }
+
+    #[test]
+    fn one_sided_entry_is_rejected() {
+        let lines = vec![line(0, Side::Credit), line(0, Side::Credit)];
+        assert_eq!(
+            check_balance(&lines),
+            Err(BalanceError::OneSided(Side::Credit))
+        );
+    }
+
+    #[test]
+    fn unbalanced_entry_is_rejected() {
+        let lines = vec![line(100, Side::Debit), line(90, Side::Credit)];
+        assert_eq!(
+            check_balance(&lines),
+            Err(BalanceError::Unbalanced {
+                debit: Amount::from_minor(100),
+                credit: Amount::from_minor(90)
+            })
+        );
+    }
+
+    #[test]
+    fn zero_entry_is_rejected() {
+        let lines = vec![line(0, Side::Debit), line(0, Side::Credit)];
+        assert_eq!(check_balance(&lines), Err(BalanceError::ZeroTotal));
+    }
+
+    #[test]
+    fn new_entry_returns_typed_error() {
+        let date = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let lines = vec![line(100, Side::Debit), line(90, Side::Credit)];
+        assert!(matches!(
+            Entry::new(date, "A1", "Test", lines, ""),
+            Err(Error::Unbalanced(BalanceError::Unbalanced { .. }))
+        ));
+    }
+}
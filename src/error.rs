@@ -0,0 +1,170 @@
+use std::{
+    fmt::{self, Display},
+    io,
+    path::PathBuf,
+};
+
+use chrono::NaiveDate;
+
+use crate::{Amount, BalanceError, Period};
+
+/// Result of the operations of the library.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Everything that can go wrong when working with a ledger.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// The directory isn't a ledger, it has no HEAD.
+    NotALedger(PathBuf),
+    /// Neither the directory nor any of its parents has a ledger.
+    LedgerNotFound(PathBuf),
+    /// A ledger can't be created in a directory that already exists.
+    LedgerExists(PathBuf),
+    /// Another process holds the lock file of the ledger.
+    Locked(PathBuf),
+    /// HEAD was moved by someone else since the ledger was opened.
+    HeadMoved { expected: String, actual: String },
+    /// No HEAD, branch, tag or hash prefix matches the ref.
+    RefNotFound(String),
+    /// More than one hash starts with the ref.
+    AmbiguousRef {
+        name: String,
+        candidates: Vec<String>,
+    },
+    /// The ref or ref name is malformed.
+    InvalidRef(String),
+    /// A branch or tag with the name already exists.
+    RefExists(String),
+    /// There is no object with the hash.
+    ObjectNotFound(String),
+    /// The object can't be decoded.
+    CorruptObject { hash: String, reason: String },
+    /// The lines of an entry don't make up a balanced voucher.
+    Unbalanced(BalanceError),
+    /// Total debits and credits differ, which a valid chain never gives.
+    UnbalancedTotals { debit: Amount, credit: Amount },
+    /// A sum doesn't fit in an amount.
+    Overflow,
+    /// The event date of an entry is outside the fiscal year of its chain.
+    OutsideFiscalYear {
+        date: NaiveDate,
+        fiscal_year: Period,
+    },
+    /// The account isn't in the chart of accounts.
+    UnknownAccount(String),
+    /// The account is in the chart of accounts but can't be used.
+    InactiveAccount { number: String, name: String },
+    /// The voucher series isn't configured.
+    UnknownSeries(String),
+    /// The entry already has a correction.
+    AlreadyCorrected { hash: String, by: String },
+    /// An argument that can't be used, i.e. a malformed series name.
+    InvalidInput(String),
+    /// A file that can't be parsed, i.e. a SIE file or chart of accounts.
+    InvalidData(String),
+    /// Reading or writing a file failed.
+    Io(io::Error),
+}
+
+impl Error {
+    /// Maps a failure to decode the object `hash` to [`Error::CorruptObject`], leaving other
+    /// I/O errors as they are.
+    pub(crate) fn corrupt(hash: &str) -> impl FnOnce(io::Error) -> Error {
+        move |e| match e.kind() {
+            io::ErrorKind::InvalidData
+            | io::ErrorKind::InvalidInput
+            | io::ErrorKind::UnexpectedEof => Error::CorruptObject {
+                hash: hash.to_string(),
+                reason: e.to_string(),
+            },
+            _ => Error::Io(e),
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NotALedger(path) => write!(
+                f,
+                "{} isn't a ledger, create one with `bok init`",
+                path.display()
+            ),
+            Error::LedgerNotFound(path) => write!(
+                f,
+                "No ledger in {} or any parent directory, create one with `bok init` or use -C or --bok-dir",
+                path.display()
+            ),
+            Error::LedgerExists(path) => write!(f, "{} already exists", path.display()),
+            Error::Locked(path) => write!(
+                f,
+                "The ledger is locked by another process, remove {} if none is running",
+                path.display()
+            ),
+            Error::HeadMoved { expected, actual } => write!(
+                f,
+                "HEAD moved from {} to {} since the ledger was opened, try again",
+                expected, actual
+            ),
+            Error::RefNotFound(name) => write!(f, "Ref not found: '{}'", name),
+            Error::AmbiguousRef { name, candidates } => write!(
+                f,
+                "'{}' matches {} entries: {}",
+                name,
+                candidates.len(),
+                candidates.join(", ")
+            ),
+            Error::InvalidRef(name) => write!(f, "Invalid ref: '{}'", name),
+            Error::RefExists(name) => write!(f, "{} already exists", name),
+            Error::ObjectNotFound(hash) => write!(f, "Object {} not found", hash),
+            Error::CorruptObject { hash, reason } => {
+                write!(f, "Object {} is corrupt: {}", hash, reason)
+            }
+            Error::Unbalanced(e) => write!(f, "Can't record entry, {}", e),
+            Error::UnbalancedTotals { debit, credit } => write!(
+                f,
+                "Trial balance doesn't balance: debit {} != credit {}",
+                debit, credit
+            ),
+            Error::Overflow => write!(f, "Amount total overflowed"),
+            Error::OutsideFiscalYear { date, fiscal_year } => write!(
+                f,
+                "Date {} is outside the fiscal year {}",
+                date, fiscal_year
+            ),
+            Error::UnknownAccount(number) => write!(f, "Unknown account: {}", number),
+            Error::InactiveAccount { number, name } => {
+                write!(f, "Account {} ({}) is inactive", number, name)
+            }
+            Error::UnknownSeries(series) => write!(f, "Unknown voucher series: {}", series),
+            Error::AlreadyCorrected { hash, by } => {
+                write!(f, "Entry {} is already corrected by {}", hash, by)
+            }
+            Error::InvalidInput(message) | Error::InvalidData(message) => f.write_str(message),
+            Error::Io(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Unbalanced(e) => Some(e),
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(value: io::Error) -> Self {
+        Error::Io(value)
+    }
+}
+
+impl From<BalanceError> for Error {
+    fn from(value: BalanceError) -> Self {
+        Error::Unbalanced(value)
+    }
+}
@@ -0,0 +1,227 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt::{self, Display},
+    io::Cursor,
+};
+
+use crate::{
+    BalanceError, Entry, Error, Ledger, Result, SeriesProblem, check_balance,
+    refs::{HEADS, TAGS},
+};
+
+/// Something wrong with the objects or refs of a ledger.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FsckProblem {
+    /// The object can't be decompressed or decoded.
+    Corrupt { hash: String, reason: String },
+    /// The content of the object doesn't hash to its file name.
+    HashMismatch { hash: String, actual: String },
+    /// The lines of the entry don't balance.
+    Unbalanced { hash: String, error: BalanceError },
+    /// The previous entry of the entry doesn't exist.
+    BrokenLink { hash: String, previous: String },
+    /// A ref points at an object that doesn't exist.
+    BrokenRef { name: String, hash: String },
+    /// The object can't be reached from HEAD, a branch or a tag.
+    Dangling { hash: String },
+    /// The voucher series of the chain at HEAD has a gap or duplicate.
+    Series(SeriesProblem),
+}
+
+impl Display for FsckProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FsckProblem::Corrupt { hash, reason } => {
+                write!(f, "corrupt object {}: {}", hash, reason)
+            }
+            FsckProblem::HashMismatch { hash, actual } => {
+                write!(f, "object {} has the content of {}", hash, actual)
+            }
+            FsckProblem::Unbalanced { hash, error } => {
+                write!(f, "unbalanced entry {}: {}", hash, error)
+            }
+            FsckProblem::BrokenLink { hash, previous } => {
+                write!(f, "entry {} links to missing {}", hash, previous)
+            }
+            FsckProblem::BrokenRef { name, hash } => {
+                write!(f, "{} points at missing {}", name, hash)
+            }
+            FsckProblem::Dangling { hash } => write!(f, "dangling object {}", hash),
+            FsckProblem::Series(problem) => problem.fmt(f),
+        }
+    }
+}
+
+impl Ledger {
+    /// Verifies every object in the ledger and the chains reachable from its refs.
+    ///
+    /// Each object is decoded and serialized again to check that it hashes to its name,
+    /// and entries are checked to balance. Every chain from HEAD, a branch or a tag must
+    /// lead back to an Origin without missing links, and on through the previous fiscal
+    /// years an Origin follows. Objects that no chain reaches are
+    /// dangling, and the voucher series at HEAD must be without gaps.
+    pub fn fsck(&mut self) -> Result<Vec<FsckProblem>> {
+        let mut problems = vec![];
+        let mut entries = BTreeMap::new();
+        for hash in self.find_hash("")? {
+            let hash = hash.as_ref().to_string();
+            let bytes = self.read_object(&hash)?;
+            let entry = match Entry::deserialize(Cursor::new(bytes)) {
+                Ok(entry) => entry,
+                Err(e) => {
+                    problems.push(FsckProblem::Corrupt {
+                        hash: hash.clone(),
+                        reason: e.to_string(),
+                    });
+                    entries.insert(hash, None);
+                    continue;
+                }
+            };
+            let actual = entry.serialize(Cursor::new(vec![]))?;
+            if actual != hash {
+                problems.push(FsckProblem::HashMismatch {
+                    hash: hash.clone(),
+                    actual,
+                });
+            }
+            if let Entry::Entry { lines, .. } = &entry
+                && let Err(error) = check_balance(lines)
+            {
+                problems.push(FsckProblem::Unbalanced {
+                    hash: hash.clone(),
+                    error,
+                });
+            }
+            entries.insert(hash, Some(entry));
+        }
+
+        let head = self.from_ref("HEAD")?.as_ref().to_string();
+        let mut refs = vec![("HEAD".to_string(), head.clone())];
+        for (prefix, list) in [(HEADS, self.branches()?), (TAGS, self.tags()?)] {
+            refs.extend(
+                list.into_iter().map(|(name, hash)| {
+                    (format!("{}/{}", prefix, name), hash.as_ref().to_string())
+                }),
+            );
+        }
+
+        let mut reachable = HashSet::new();
+        for (name, hash) in refs {
+            if !entries.contains_key(&hash) {
+                problems.push(FsckProblem::BrokenRef { name, hash });
+                continue;
+            }
+            let mut next_hash = hash;
+            while reachable.insert(next_hash.clone()) {
+                // The Origin of a year following another links to the end of that year.
+                let previous = match entries.get(&next_hash) {
+                    Some(Some(Entry::Entry { previous_entry, .. })) => previous_entry,
+                    Some(Some(Entry::Origin {
+                        previous_year: Some(previous_year),
+                        ..
+                    })) => previous_year,
+                    _ => break,
+                };
+                if !entries.contains_key(previous) {
+                    problems.push(FsckProblem::BrokenLink {
+                        hash: next_hash,
+                        previous: previous.clone(),
+                    });
+                    break;
+                }
+                next_hash = previous.clone();
+            }
+        }
+        problems.extend(
+            entries
+                .keys()
+                .filter(|hash| !reachable.contains(*hash))
+                .map(|hash| FsckProblem::Dangling { hash: hash.clone() }),
+        );
+
+        // A chain with corrupt or missing entries is reported above and has no series to check.
+        match self.check_series(self.from_ref("HEAD")?) {
+            Ok(series) => problems.extend(series.into_iter().map(FsckProblem::Series)),
+            Err(Error::CorruptObject { .. } | Error::ObjectNotFound(_)) => {}
+            Err(e) => return Err(e),
+        }
+        Ok(problems)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::{Amount, EntryLine, Side, objects::Objects};
+
+    fn lines(debit: i64, credit: i64) -> Vec<EntryLine> {
+        vec![
+            EntryLine::new("5410", Amount::from_minor(debit), Side::Debit, None),
+            EntryLine::new("1930", Amount::from_minor(credit), Side::Credit, None),
+        ]
+    }
+
+    fn write_object(dir: &std::path::Path, entry: &Entry) -> Result<String> {
+        let mut buffer = Cursor::new(vec![]);
+        let hash = entry.serialize(&mut buffer)?;
+        Objects::open(dir.join("objects"))?.write(&hash, &buffer.into_inner())?;
+        Ok(hash)
+    }
+
+    #[test]
+    fn finds_problems() -> Result<()> {
+        let dir = env::temp_dir().join("bok_fsck");
+        let _ = fs::remove_dir_all(&dir);
+        let mut ledger = Ledger::init(2025, dir.clone())?;
+        let date = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let first = ledger.add_voucher_on_date(date, "A", "Första", lines(100, 100))?;
+        assert_eq!(ledger.fsck()?, vec![]);
+
+        let entry = |previous: &str, debit| Entry::Entry {
+            timestamp: chrono::DateTime::from_timestamp(0, 0).unwrap(),
+            event_date: date,
+            name: "A3".to_string(),
+            description: "Test".to_string(),
+            lines: lines(debit, 100),
+            previous_entry: previous.to_string(),
+        };
+        let missing = "0".repeat(64);
+        let unbalanced = write_object(&dir, &entry(first.as_ref(), 50))?;
+        ledger.create_tag(
+            "broken",
+            &ledger.from_ref(&write_object(&dir, &entry(&missing, 100))?)?,
+        )?;
+        let objects = Objects::open(dir.join("objects"))?;
+        objects.write("ffff", &[0x1f, 0x8b, 0x08, 0x00, 0x00])?;
+        objects.write("eeee", &objects.read(first.as_ref())?)?;
+
+        let problems: Vec<String> = ledger.fsck()?.iter().map(|p| p.to_string()).collect();
+        assert!(
+            problems
+                .iter()
+                .any(|p| p.starts_with("corrupt object ffff"))
+        );
+        assert!(problems.contains(&format!(
+            "object eeee has the content of {}",
+            first.as_ref()
+        )));
+        assert!(
+            problems
+                .iter()
+                .any(|p| p.starts_with(&format!("unbalanced entry {}", unbalanced)))
+        );
+        assert!(
+            problems
+                .iter()
+                .any(|p| p.ends_with(&format!("links to missing {}", missing)))
+        );
+        assert!(problems.contains(&format!("dangling object {}", unbalanced)));
+        assert!(!problems.contains(&format!("dangling object {}", first.as_ref())));
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
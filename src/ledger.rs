@@ -1,19 +1,37 @@
 use std::{
-    collections::{HashMap, hash_map::Entry as HashEntry},
-    fs::{self, create_dir_all, read, write},
-    io::{Cursor, Error, ErrorKind, Result},
-    path::PathBuf,
+    collections::{BTreeMap, HashMap},
+    fs::{create_dir_all, read_to_string},
+    io::{Cursor, ErrorKind},
+    path::{Path, PathBuf},
 };
 
-use chrono::{Local, NaiveDate, Utc};
+use chrono::{Local, Months, NaiveDate, Utc};
 
-use crate::{Entry, EntryLine};
+use crate::{
+    ChartOfAccounts, Entry, EntryLine, Error, LogFilter, Period, Result, SeriesProblem,
+    TrialBalance, VoucherNumber,
+    atomic::{Lock, write_atomic},
+    objects::Objects,
+    refs::{DEFAULT_BRANCH, HEADS, Head, NO_HASH, ReflogEntry, Refs, TAGS, check_name},
+    voucher::{DEFAULT_SERIES, check_series_name, series_problems},
+};
 
 pub struct Ledger {
     head: Entry,
     head_hash: String,
-    object_path: PathBuf,
-    head_path: PathBuf,
+    location: PathBuf,
+    objects: Objects,
+    refs: Refs,
+    chart: ChartOfAccounts,
+    /// Configured voucher series and their descriptions.
+    series: BTreeMap<String, String>,
+    /// Last voucher number per series used before the first entry of the first fiscal year,
+    /// for books imported in the middle of a series.
+    imported_counters: BTreeMap<String, u32>,
+    /// Last used number per voucher series, read from the chain on first use.
+    voucher_counters: Option<BTreeMap<String, u32>>,
+    /// Fiscal year of the chain at HEAD, read from its Origin on first use.
+    fiscal_year: Option<Period>,
 
     hash_map: HashMap<String, Entry>,
 }
@@ -28,57 +46,312 @@ impl AsRef<str> for EntryHash {
     }
 }
 
+/// Iterator over the entries of a chain, see [`Ledger::history`].
+pub struct History<'a> {
+    ledger: &'a Ledger,
+    next: Option<EntryHash>,
+}
+
+impl Iterator for History<'_> {
+    type Item = Result<(EntryHash, Entry)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let hash = self.next.take()?;
+        let entry = match self.ledger.hash_map.get(&hash.0) {
+            Some(entry) => entry.clone(),
+            None => match self.ledger.load_entry(&hash) {
+                Ok(entry) => entry,
+                Err(e) => return Some(Err(e)),
+            },
+        };
+        if let Entry::Entry { previous_entry, .. } = &entry {
+            self.next = Some(EntryHash(previous_entry.clone()));
+        }
+        Some(Ok((hash, entry)))
+    }
+}
+
 impl Ledger {
+    /// Creates a ledger whose first fiscal year is the calendar year `year`.
     pub fn init(year: usize, location: PathBuf) -> Result<Self> {
+        let date = |m, d| {
+            i32::try_from(year)
+                .ok()
+                .and_then(|year| NaiveDate::from_ymd_opt(year, m, d))
+                .ok_or_else(|| Error::InvalidInput(format!("Invalid year: {}", year)))
+        };
+        Self::init_fiscal_year(date(1, 1)?, date(12, 31)?, location)
+    }
+
+    /// Creates a ledger whose first fiscal year runs from `start` to `end`, both included,
+    /// i.e. a broken fiscal year from 2025-07-01 to 2026-06-30.
+    ///
+    /// A fiscal year is at most 18 months long, as the first or a changed fiscal year may
+    /// be in Sweden.
+    pub fn init_fiscal_year(start: NaiveDate, end: NaiveDate, location: PathBuf) -> Result<Self> {
+        let fiscal_year = Period::new(Some(start), Some(end));
+        if end < start
+            || start
+                .checked_add_months(Months::new(18))
+                .is_none_or(|max| end >= max)
+        {
+            return Err(Error::InvalidInput(format!(
+                "Invalid fiscal year {}, it must be at most 18 months",
+                fiscal_year
+            )));
+        }
         if location.is_dir() {
-            return Err(Error::new(
-                ErrorKind::DirectoryNotEmpty,
-                "Directory isn't empty",
-            ));
+            return Err(Error::LedgerExists(location));
         }
         create_dir_all(&location)?;
 
         let head = Entry::Origin {
             timestamp: Utc::now(),
-            year: year as u64,
+            start,
+            end,
+            previous_year: None,
         };
         let mut buffer = Cursor::new(vec![]);
         let hash = head.serialize(&mut buffer)?;
-        let head_path = location.join("HEAD");
-        write(&head_path, &hash)?;
-        let object_path = location.join("objects");
-        create_dir_all(&object_path)?;
-        write(object_path.join(&hash), buffer.into_inner())?;
+        let refs = Refs::new(location.clone());
+        let branch = format!("{}/{}", HEADS, DEFAULT_BRANCH);
+        refs.write(&branch, &hash)?;
+        refs.set_head(&Head::Symbolic(branch))?;
+        let objects = Objects::open(location.join("objects"))?;
+        objects.write(&hash, &buffer.into_inner())?;
+        refs.log_head(
+            NO_HASH,
+            &hash,
+            &format!("init: fiscal year {}", fiscal_year),
+        )?;
         Ok(Self {
             head,
             head_hash: hash,
-            head_path,
-            object_path,
+            location,
+            refs,
+            objects,
+            chart: ChartOfAccounts::new(),
+            series: default_series(),
+            imported_counters: BTreeMap::new(),
+            voucher_counters: None,
+            fiscal_year: None,
             hash_map: HashMap::new(),
         })
     }
 
+    /// Name of the ledger directory, created in the current directory by `bok init`.
+    pub const DIR_NAME: &'static str = ".bok";
+
+    /// Finds the ledger directory in `start` or the closest parent directory that has one,
+    /// the way git finds `.git`.
+    pub fn discover(start: &Path) -> Result<PathBuf> {
+        start
+            .ancestors()
+            .map(|dir| dir.join(Self::DIR_NAME))
+            .find(|location| location.join("HEAD").is_file())
+            .ok_or_else(|| Error::LedgerNotFound(start.to_path_buf()))
+    }
+
     pub fn from_dir(location: PathBuf) -> Result<Self> {
-        if !location.is_dir() {
-            return Err(Error::new(
-                std::io::ErrorKind::NotADirectory,
-                "Directory doesn't exist",
-            ));
+        if !location.join("HEAD").is_file() {
+            return Err(Error::NotALedger(location));
+        }
+        let refs = Refs::new(location.clone());
+        let head_hash = refs.head_hash()?;
+        let objects = Objects::open(location.join("objects"))?;
+        let head = Entry::deserialize(Cursor::new(objects.read(&head_hash)?))
+            .map_err(Error::corrupt(&head_hash))?;
+        let chart = match read_optional(&location.join("CHART"))? {
+            Some(chart_hash) => ChartOfAccounts::deserialize(&read_to_string(
+                location.join("chart").join(chart_hash.trim()),
+            )?)?,
+            None => ChartOfAccounts::new(),
+        };
+        let mut series = default_series();
+        let mut imported_counters = BTreeMap::new();
+        if let Some(content) = read_optional(&location.join("series"))? {
+            series.clear();
+            for line in content.lines() {
+                let mut fields = line.splitn(3, '\t');
+                let name = fields.next().unwrap_or_default().to_string();
+                series.insert(name.clone(), fields.next().unwrap_or_default().to_string());
+                if let Some(counter) = fields.next() {
+                    let counter = counter.parse().map_err(|_| {
+                        Error::InvalidData(format!("Invalid voucher series line: {}", line))
+                    })?;
+                    imported_counters.insert(name, counter);
+                }
+            }
         }
-        let head_path = location.join("HEAD");
-        let head_hash = String::from_utf8(read(&head_path)?)
-            .map_err(|_| Error::new(ErrorKind::InvalidData, "Couldn't parse HEAD file..."))?;
-        let object_path = location.join("objects");
-        let head = Entry::from_file(&object_path.join(&head_hash))?;
         Ok(Self {
             head,
             head_hash,
-            object_path,
-            head_path,
+            location,
+            objects,
+            refs,
+            chart,
+            series,
+            imported_counters,
+            voucher_counters: None,
+            fiscal_year: None,
             hash_map: HashMap::new(),
         })
     }
 
+    /// The current chart of accounts, empty if none has been set.
+    pub fn chart(&self) -> &ChartOfAccounts {
+        &self.chart
+    }
+
+    /// Stores a new version of the chart of accounts.
+    ///
+    /// Every version is kept under `chart/` and links to the one it replaced, while the
+    /// `CHART` file points at the current one. Returns the hash of the new version.
+    pub fn set_chart(&mut self, chart: ChartOfAccounts) -> Result<String> {
+        let _lock = self.lock()?;
+        let chart_file = self.location.join("CHART");
+        let previous = read_optional(&chart_file)?;
+        let (hash, content) = chart.serialize(previous.as_deref().map(str::trim))?;
+        let chart_dir = self.location.join("chart");
+        create_dir_all(&chart_dir)?;
+        write_atomic(&chart_dir.join(&hash), content)?;
+        write_atomic(&chart_file, &hash)?;
+        self.chart = chart;
+        Ok(hash)
+    }
+
+    /// The configured voucher series and their descriptions.
+    pub fn series(&self) -> &BTreeMap<String, String> {
+        &self.series
+    }
+
+    /// Adds, or changes the description of, a voucher series.
+    pub fn add_series(&mut self, series: &str, description: &str) -> Result<()> {
+        check_series_name(series)?;
+        if description.contains(['\t', '\n', '\r']) {
+            return Err(Error::InvalidInput(
+                "A series description can't contain tabs or line breaks".to_string(),
+            ));
+        }
+        let _lock = self.lock()?;
+        self.series
+            .insert(series.to_string(), description.to_string());
+        self.write_series()
+    }
+
+    /// Adds the series of vouchers numbered by other software, i.e. `B7` from an imported
+    /// SIE file, remembering the numbers used before the first of them.
+    pub(crate) fn import_series(
+        &mut self,
+        vouchers: impl IntoIterator<Item = VoucherNumber>,
+    ) -> Result<()> {
+        let _lock = self.lock()?;
+        for voucher in vouchers {
+            if check_series_name(&voucher.series).is_err() {
+                continue;
+            }
+            self.series.entry(voucher.series.clone()).or_default();
+            let counter = voucher.number.saturating_sub(1);
+            let used = self
+                .imported_counters
+                .entry(voucher.series)
+                .or_insert(counter);
+            *used = counter.min(*used);
+        }
+        self.imported_counters.retain(|_, counter| *counter > 0);
+        self.write_series()
+    }
+
+    /// Writes the `series` file, one `<series>\t<description>` line per series followed by
+    /// `\t<counter>` for a series imported in the middle of the first fiscal year.
+    fn write_series(&self) -> Result<()> {
+        let content: String = self
+            .series
+            .iter()
+            .map(
+                |(series, description)| match self.imported_counters.get(series) {
+                    Some(counter) => format!("{}\t{}\t{}\n", series, description, counter),
+                    None => format!("{}\t{}\n", series, description),
+                },
+            )
+            .collect();
+        Ok(write_atomic(&self.location.join("series"), content)?)
+    }
+
+    /// Voucher numbers already used in each series before the first entry of the chain
+    /// from `hash`, which is only ever the case in the first fiscal year of an imported book.
+    fn used_before(&self, hash: &EntryHash) -> Result<BTreeMap<String, u32>> {
+        Ok(match self.previous_year(hash)? {
+            Some(_) => BTreeMap::new(),
+            None => self.imported_counters.clone(),
+        })
+    }
+
+    /// The number the next voucher in `series` will get.
+    pub fn next_voucher(&mut self, series: &str) -> Result<VoucherNumber> {
+        if !self.series.contains_key(series) {
+            return Err(Error::UnknownSeries(series.to_string()));
+        }
+        let counters = match self.voucher_counters.take() {
+            Some(counters) => counters,
+            None => {
+                let head = EntryHash(self.head_hash.clone());
+                let mut counters = self.used_before(&head)?;
+                for item in self.history(&head) {
+                    if let (_, Entry::Entry { name, .. }) = item?
+                        && let Some(voucher) = VoucherNumber::parse(&name)
+                    {
+                        let last = counters.entry(voucher.series).or_insert(0);
+                        *last = voucher.number.max(*last);
+                    }
+                }
+                counters
+            }
+        };
+        let last = counters.get(series).copied().unwrap_or(0);
+        self.voucher_counters = Some(counters);
+        Ok(VoucherNumber::new(series, last + 1))
+    }
+
+    /// Records an entry dated today as the next voucher in `series`.
+    pub fn add_voucher(
+        &mut self,
+        series: &str,
+        description: &str,
+        lines: Vec<EntryLine>,
+    ) -> Result<EntryHash> {
+        self.add_voucher_on_date(Local::now().date_naive(), series, description, lines)
+    }
+
+    /// Records an entry as the next voucher in `series`, i.e. `A13` after `A12`.
+    pub fn add_voucher_on_date(
+        &mut self,
+        date: NaiveDate,
+        series: &str,
+        description: &str,
+        lines: Vec<EntryLine>,
+    ) -> Result<EntryHash> {
+        let voucher = self.next_voucher(series)?;
+        self.add_entry_on_date(date, &voucher.to_string(), description, lines)
+    }
+
+    /// Finds gaps and duplicates in the voucher series of the chain from `hash`.
+    pub fn check_series(&self, hash: EntryHash) -> Result<Vec<SeriesProblem>> {
+        let mut vouchers = vec![];
+        for item in self.history(&hash) {
+            if let (hash, Entry::Entry { name, .. }) = item?
+                && let Some(voucher) = VoucherNumber::parse(&name)
+            {
+                vouchers.push((voucher, hash.0));
+            }
+        }
+        vouchers.reverse();
+        Ok(series_problems(
+            vouchers.iter().map(|(v, hash)| (v.clone(), hash.as_str())),
+            &self.used_before(&hash)?,
+        ))
+    }
+
     pub fn add_entry(
         &mut self,
         name: &str,
@@ -95,69 +368,825 @@ impl Ledger {
         description: &str,
         lines: Vec<EntryLine>,
     ) -> Result<EntryHash> {
-        let new_head = Entry::new(date, name, description, lines, &self.head_hash);
+        let new_head = self.new_entry(date, name, description, lines)?;
+        let hash = self.advance_head(new_head, &format!("record: {} {}", name, description))?;
+        if let (Some(counters), Some(voucher)) =
+            (&mut self.voucher_counters, VoucherNumber::parse(name))
+        {
+            let last = counters.entry(voucher.series).or_insert(0);
+            *last = voucher.number.max(*last);
+        }
+        Ok(hash)
+    }
+
+    /// Builds an entry following HEAD without writing it, checking that the date is within
+    /// the fiscal year and that the accounts can be used.
+    pub(crate) fn new_entry(
+        &mut self,
+        date: NaiveDate,
+        name: &str,
+        description: &str,
+        lines: Vec<EntryLine>,
+    ) -> Result<Entry> {
+        let fiscal_year = match self.fiscal_year {
+            Some(fiscal_year) => fiscal_year,
+            None => self.fiscal_year(EntryHash(self.head_hash.clone()))?,
+        };
+        self.fiscal_year = Some(fiscal_year);
+        if !fiscal_year.contains(date) {
+            return Err(Error::OutsideFiscalYear { date, fiscal_year });
+        }
+        self.check_accounts(&lines)?;
+        Entry::new(date, name, description, lines, &self.head_hash)
+    }
+
+    /// Checks that every account of the lines can be used according to the chart.
+    pub(crate) fn check_accounts(&self, lines: &[EntryLine]) -> Result<()> {
+        for line in lines {
+            self.chart.check_usable(&line.account)?;
+        }
+        Ok(())
+    }
+
+    /// Writes `entry` and moves HEAD to it, unless HEAD was moved since the ledger was opened.
+    pub(crate) fn advance_head(&mut self, entry: Entry, message: &str) -> Result<EntryHash> {
+        let lock = self.lock()?;
+        self.advance_head_locked(&lock, entry, message)
+    }
+
+    /// Same as [`Ledger::advance_head`] under a lock that is already held, so several
+    /// entries can be written without another process writing in between.
+    ///
+    /// An Origin starts a new chain, so the fiscal year and voucher numbers start over.
+    pub(crate) fn advance_head_locked(
+        &mut self,
+        _lock: &Lock,
+        entry: Entry,
+        message: &str,
+    ) -> Result<EntryHash> {
+        let current = self.refs.head_hash()?;
+        if current != self.head_hash {
+            return Err(Error::HeadMoved {
+                expected: self.head_hash.clone(),
+                actual: current,
+            });
+        }
         let mut buffer = Cursor::new(vec![]);
-        let hash = new_head.serialize(&mut buffer)?;
-        let path = self.object_path.join(&hash);
-        create_dir_all(&self.object_path)?;
-        write(path, buffer.into_inner())?;
-        write(&self.head_path, &hash)?;
+        let hash = entry.serialize(&mut buffer)?;
+        self.objects.write(&hash, &buffer.into_inner())?;
+        self.refs.update_head(&hash)?;
+        self.refs.log_head(&current, &hash, message)?;
+        if let Entry::Origin { .. } = entry {
+            self.fiscal_year = None;
+            self.voucher_counters = Some(BTreeMap::new());
+        }
         self.head_hash = hash;
-        self.head = new_head;
+        self.head = entry;
         Ok(EntryHash(self.head_hash.clone()))
     }
 
+    /// Resolves a REF to the hash of an entry.
+    ///
+    /// A REF is `HEAD`, a branch, a tag, a full ref like `refs/tags/q1` or a hash prefix,
+    /// optionally followed by any number of suffixes:
+    /// - `~N` steps N entries back, `~` alone is one step.
+    /// - `^` steps one entry back.
+    /// - `@{YYYY-MM-DD}` finds the newest entry with an event date on or before the date,
+    ///   or the Origin if there is none.
+    /// - `@{N}` directly after `HEAD` is where HEAD pointed N movements ago, see [`Ledger::reflog`].
+    ///
+    /// Stepping back from the Origin of a year following another continues at the last
+    /// entry of that year, the same way for dates before the year.
+    ///
+    /// I.e. `HEAD~3`, `main^^`, `HEAD@{1}` or `q1-closed@{2025-03-31}`. Without a name `HEAD` is used.
     pub fn from_ref(&self, entry_ref: &str) -> Result<EntryHash> {
+        let split = entry_ref.find(['~', '^', '@']).unwrap_or(entry_ref.len());
+        let (base, mut suffixes) = entry_ref.split_at(split);
+        let mut hash = match base {
+            "" => EntryHash(self.head_hash.clone()),
+            base => self.resolve_name(base)?,
+        };
+        let bad_suffix = || Error::InvalidRef(entry_ref.to_string());
+        while !suffixes.is_empty() {
+            let (steps, rest) = if let Some(rest) = suffixes.strip_prefix('~') {
+                let digits =
+                    rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+                let (n, rest) = rest.split_at(digits);
+                (
+                    if n.is_empty() {
+                        1
+                    } else {
+                        n.parse().map_err(|_| bad_suffix())?
+                    },
+                    rest,
+                )
+            } else if let Some(rest) = suffixes.strip_prefix('^') {
+                // Entries have a single parent, so only `^`, `^1` and `^0` make sense.
+                match rest.strip_prefix('0') {
+                    Some(rest) => (0, rest),
+                    None => (1, rest.strip_prefix('1').unwrap_or(rest)),
+                }
+            } else if let Some(rest) = suffixes.strip_prefix("@{") {
+                let (at, rest) = rest.split_once('}').ok_or_else(bad_suffix)?;
+                hash = match at.parse::<usize>() {
+                    Ok(n)
+                        if matches!(base, "" | "HEAD")
+                            && suffixes.len() == entry_ref.len() - split =>
+                    {
+                        let reflog = self.reflog()?;
+                        let entry = reflog
+                            .get(n)
+                            .ok_or_else(|| Error::RefNotFound(entry_ref.to_string()))?;
+                        EntryHash(entry.new.clone())
+                    }
+                    // Only HEAD has a reflog.
+                    Ok(_) => return Err(bad_suffix()),
+                    Err(_) => self.entry_at_date(hash, at.parse().map_err(|_| bad_suffix())?)?,
+                };
+                suffixes = rest;
+                continue;
+            } else {
+                return Err(bad_suffix());
+            };
+            for _ in 0..steps {
+                hash = match self.load_entry(&hash)? {
+                    Entry::Entry { previous_entry, .. } => EntryHash(previous_entry),
+                    Entry::Origin {
+                        previous_year: Some(previous_year),
+                        ..
+                    } => EntryHash(previous_year),
+                    Entry::Origin { .. } => return Err(Error::RefNotFound(entry_ref.to_string())),
+                };
+            }
+            suffixes = rest;
+        }
+        Ok(hash)
+    }
+
+    /// Resolves a ref without suffixes.
+    fn resolve_name(&self, entry_ref: &str) -> Result<EntryHash> {
         if entry_ref == "HEAD" {
             return Ok(EntryHash(self.head_hash.clone()));
         }
+        if let Some(hash) = self.refs.resolve(entry_ref)? {
+            return Ok(EntryHash(hash));
+        }
         match &self.find_hash(entry_ref)?[..] {
-            [] => Err(Error::new(ErrorKind::NotFound, "ref not found")),
+            [] => Err(Error::RefNotFound(entry_ref.to_string())),
             [entry_hash] => Ok(entry_hash.clone()),
-            _ => Err(Error::new(ErrorKind::TooManyLinks, "To many refs match")),
+            candidates => Err(Error::AmbiguousRef {
+                name: entry_ref.to_string(),
+                candidates: candidates.iter().map(|h| h.0.clone()).collect(),
+            }),
+        }
+    }
+
+    /// The newest entry from `hash` back with an event date on or before `date`.
+    fn entry_at_date(&self, hash: EntryHash, date: NaiveDate) -> Result<EntryHash> {
+        let mut hash = hash;
+        loop {
+            match self.load_entry(&hash)? {
+                Entry::Entry { event_date, .. } if event_date <= date => return Ok(hash),
+                Entry::Entry { previous_entry, .. } => hash = EntryHash(previous_entry),
+                Entry::Origin {
+                    previous_year: Some(previous_year),
+                    start,
+                    ..
+                } if date < start => hash = EntryHash(previous_year),
+                Entry::Origin { .. } => return Ok(hash),
+            }
+        }
+    }
+
+    /// Locks the ledger directory against other processes until the lock is dropped.
+    ///
+    /// Objects in the flat layout of older versions are moved into their fan-out
+    /// directories first, so only commands that write change the layout.
+    pub(crate) fn lock(&self) -> Result<Lock> {
+        let lock = Lock::acquire(&self.location)?;
+        if !self.objects.flat()?.is_empty() {
+            self.objects.migrate()?;
+        }
+        Ok(lock)
+    }
+
+    /// What HEAD points at, a branch or an entry.
+    pub fn head(&self) -> Result<Head> {
+        self.refs.head()
+    }
+
+    /// Every movement of HEAD, newest first, so `HEAD@{N}` is the N:th entry.
+    pub fn reflog(&self) -> Result<Vec<ReflogEntry>> {
+        self.refs.head_log()
+    }
+
+    /// Creates a branch at `hash`, i.e. to draft a scenario next to `main`.
+    pub fn create_branch(&mut self, name: &str, hash: &EntryHash) -> Result<()> {
+        self.create_ref(HEADS, name, hash)
+    }
+
+    /// Tags the entry at `hash`, i.e. to mark a closed period.
+    pub fn create_tag(&mut self, name: &str, hash: &EntryHash) -> Result<()> {
+        self.create_ref(TAGS, name, hash)
+    }
+
+    fn create_ref(&mut self, prefix: &str, name: &str, hash: &EntryHash) -> Result<()> {
+        check_name(name)?;
+        let _lock = self.lock()?;
+        let full_name = format!("{}/{}", prefix, name);
+        if self.refs.read(&full_name)?.is_some() {
+            return Err(Error::RefExists(full_name));
         }
+        self.refs.write(&full_name, &hash.0)
+    }
+
+    /// All branches as short names and the entries they point at.
+    pub fn branches(&self) -> Result<Vec<(String, EntryHash)>> {
+        self.list_refs(HEADS)
+    }
+
+    /// All tags as short names and the entries they point at.
+    pub fn tags(&self) -> Result<Vec<(String, EntryHash)>> {
+        self.list_refs(TAGS)
+    }
+
+    fn list_refs(&self, prefix: &str) -> Result<Vec<(String, EntryHash)>> {
+        Ok(self
+            .refs
+            .list(prefix)?
+            .into_iter()
+            .map(|(name, hash)| (name, EntryHash(hash)))
+            .collect())
+    }
+
+    /// Moves HEAD to a branch, so new entries extend it, or detaches it at any other ref.
+    pub fn checkout(&mut self, entry_ref: &str) -> Result<Head> {
+        let _lock = self.lock()?;
+        let branch = format!("{}/{}", HEADS, entry_ref);
+        let head = if check_name(entry_ref).is_ok() && self.refs.read(&branch)?.is_some() {
+            Head::Symbolic(branch)
+        } else {
+            Head::Detached(self.from_ref(entry_ref)?.0)
+        };
+        let current = self.refs.head()?;
+        let from = match (current.branch(), &current) {
+            (Some(branch), _) => branch.to_string(),
+            (None, Head::Symbolic(name) | Head::Detached(name)) => name.clone(),
+        };
+        let old = self.refs.head_hash()?;
+        self.refs.set_head(&head)?;
+        self.head_hash = self.refs.head_hash()?;
+        self.refs.log_head(
+            &old,
+            &self.head_hash,
+            &format!("checkout: moving from {} to {}", from, entry_ref),
+        )?;
+        self.head = self.load_entry(&EntryHash(self.head_hash.clone()))?;
+        self.voucher_counters = None;
+        self.fiscal_year = None;
+        Ok(head)
     }
 
     pub fn find_hash(&self, hash: &str) -> Result<Vec<EntryHash>> {
-        fs::read_dir(&self.object_path)?
-            .map(|r| {
-                r.and_then(|d| {
-                    d.file_name()
-                        .into_string()
-                        .map_err(|_| Error::new(std::io::ErrorKind::InvalidData, "BAD!"))
-                })
-            })
-            .filter_map(|r| match r {
-                Ok(s) if s.starts_with(hash) => Some(Ok(EntryHash(s))),
-                Ok(_) => None,          // Skip entries that don't match
-                Err(e) => Some(Err(e)), // Propagate errors
-            })
-            .collect()
+        Ok(self
+            .objects
+            .find(hash)?
+            .into_iter()
+            .map(EntryHash)
+            .collect())
+    }
+
+    /// The entries from `from` back to and including the Origin, newest first.
+    ///
+    /// Follows `previous_entry` directly, so only the objects on the way are read.
+    /// ```ignore
+    /// for item in ledger.history(&ledger.from_ref("HEAD")?) {
+    ///     let (hash, entry) = item?;
+    /// }
+    /// ```
+    pub fn history(&self, from: &EntryHash) -> History<'_> {
+        History {
+            ledger: self,
+            next: Some(from.clone()),
+        }
     }
 
     pub fn get_entry(&mut self, hash: &EntryHash) -> Result<&Entry> {
-        match self.hash_map.entry(hash.0.clone()) {
-            HashEntry::Vacant(ve) => {
-                let entry_file = self.object_path.join(hash.0.clone());
-                let entry_ref = ve.insert(Entry::from_file(&entry_file)?);
-                Ok(entry_ref)
+        if !self.hash_map.contains_key(&hash.0) {
+            let entry = self.load_entry(hash)?;
+            self.hash_map.insert(hash.0.clone(), entry);
+        }
+        Ok(&self.hash_map[&hash.0])
+    }
+
+    /// Reads an entry from disk, bypassing the cache.
+    fn load_entry(&self, hash: &EntryHash) -> Result<Entry> {
+        Entry::deserialize(Cursor::new(self.read_object(&hash.0)?)).map_err(Error::corrupt(&hash.0))
+    }
+
+    /// Consolidates all objects into a single pack, returning the number of objects in it.
+    pub fn gc(&mut self) -> Result<usize> {
+        let _lock = self.lock()?;
+        self.objects.pack()
+    }
+
+    /// Reads the stored bytes of an object as they are on disk.
+    pub(crate) fn read_object(&self, hash: &str) -> Result<Vec<u8>> {
+        self.objects.read(hash)
+    }
+
+    /// The entries from `hash` back to the Origin that match the filter, newest first.
+    pub fn log(&self, hash: &EntryHash, filter: &LogFilter) -> Result<Vec<(EntryHash, Entry)>> {
+        let mut entries = vec![];
+        for item in self.history(hash) {
+            if filter.limit.is_some_and(|limit| entries.len() >= limit) {
+                break;
+            }
+            let (hash, entry) = item?;
+            if filter.matches(&entry) {
+                entries.push((hash, entry));
             }
-            HashEntry::Occupied(o) => Ok(o.into_mut()),
         }
+        Ok(entries)
     }
 
-    pub fn show_log(&mut self, hash: EntryHash) -> Result<String> {
-        let mut next_hash = hash;
+    /// One line per entry from `hash` back to the Origin that matches the filter.
+    pub fn show_log(&self, hash: EntryHash, filter: &LogFilter) -> Result<String> {
+        let corrections = self.corrections()?;
         let mut result = String::new();
-
-        while let entry @ Entry::Entry { previous_entry, .. } = self.get_entry(&next_hash)? {
-            result += &entry.show_short()?;
-            let next_ref = previous_entry.clone();
-            next_hash = self.from_ref(&next_ref)?;
+        for (hash, entry) in self.log(&hash, filter)? {
+            let line = entry.show_short()?;
+            match corrections.get(&hash.0) {
+                Some(by) => result += &format!("{} [corrected by {}]\n", line.trim_end(), &by[..6]),
+                None => result += &line,
+            }
         }
+        Ok(result)
+    }
 
-        let last_entry = self.get_entry(&next_hash)?;
-        result += &last_entry.show_short()?;
+    /// The entry at `hash` with account names from the chart, followed by the entry
+    /// correcting it if there is one.
+    pub fn show(&self, hash: &EntryHash) -> Result<String> {
+        let mut result = self.load_entry(hash)?.show_with_chart(&self.chart);
+        if let Some(by) = self.corrected_by(hash)? {
+            result += &format!("\nCorrected by {}\n", by.0);
+        }
         Ok(result)
     }
+
+    /// Maps every corrected entry in the chain at HEAD to the entry correcting it.
+    fn corrections(&self) -> Result<HashMap<String, String>> {
+        let mut corrections = HashMap::new();
+        for item in self.history(&EntryHash(self.head_hash.clone())) {
+            let (hash, entry) = item?;
+            if let Some(corrected) = entry.corrects() {
+                corrections.insert(corrected.to_string(), hash.0);
+            }
+        }
+        Ok(corrections)
+    }
+
+    /// The entry in the chain at HEAD that corrects `hash`, if any.
+    pub fn corrected_by(&self, hash: &EntryHash) -> Result<Option<EntryHash>> {
+        Ok(self.corrections()?.remove(&hash.0).map(EntryHash))
+    }
+
+    /// Records a correcting voucher for the entry at `hash`, with every line on the opposite side.
+    ///
+    /// Since entries are immutable this is the way to undo a mistake, and the correction is
+    /// numbered in `series` like any other voucher.
+    pub fn revert(&mut self, hash: &EntryHash, date: NaiveDate, series: &str) -> Result<EntryHash> {
+        if let Some(by) = self.corrected_by(hash)? {
+            return Err(Error::AlreadyCorrected {
+                hash: hash.0.clone(),
+                by: by.0,
+            });
+        }
+        let Entry::Entry {
+            description, lines, ..
+        } = self.get_entry(hash)?.clone()
+        else {
+            return Err(Error::InvalidInput(
+                "The Origin can't be reverted".to_string(),
+            ));
+        };
+        let lines = lines
+            .into_iter()
+            .map(|line| EntryLine {
+                side: line.side.opposite(),
+                ..line
+            })
+            .collect();
+        let description = format!("{}{}: {}", Entry::CORRECTION_OF, hash.0, description);
+        self.add_voucher_on_date(date, series, &description, lines)
+    }
+
+    /// Sums debits and credits per account for every entry from `hash` back to the Origin
+    /// with an event date within `period`.
+    pub fn trial_balance(&self, hash: EntryHash, period: Period) -> Result<TrialBalance> {
+        let mut balance = TrialBalance::new();
+        for item in self.history(&hash) {
+            if let (
+                _,
+                Entry::Entry {
+                    event_date, lines, ..
+                },
+            ) = item?
+                && period.contains(event_date)
+            {
+                for line in &lines {
+                    balance.add_line(line)?;
+                }
+            }
+        }
+        Ok(balance)
+    }
+
+    /// The fiscal year of the chain that `hash` is part of, taken from its Origin.
+    pub fn fiscal_year(&self, hash: EntryHash) -> Result<Period> {
+        match self.history(&hash).last() {
+            Some(Ok((_, Entry::Origin { start, end, .. }))) => {
+                Ok(Period::new(Some(start), Some(end)))
+            }
+            Some(Err(e)) => Err(e),
+            _ => unreachable!("history always ends at an Origin or an error"),
+        }
+    }
+
+    /// The last entry of the fiscal year before the one `hash` is part of, taken from its
+    /// Origin. The first fiscal year of a ledger has none.
+    pub fn previous_year(&self, hash: &EntryHash) -> Result<Option<EntryHash>> {
+        match self.history(hash).last() {
+            Some(Ok((_, Entry::Origin { previous_year, .. }))) => Ok(previous_year.map(EntryHash)),
+            Some(Err(e)) => Err(e),
+            _ => unreachable!("history always ends at an Origin or an error"),
+        }
+    }
+}
+
+fn default_series() -> BTreeMap<String, String> {
+    BTreeMap::from([(DEFAULT_SERIES.to_string(), String::new())])
+}
+
+/// Reads a file that might not exist yet.
+fn read_optional(path: &Path) -> Result<Option<String>> {
+    match read_to_string(path) {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use super::*;
+    use crate::{Amount, BalanceError, Side};
+
+    fn ledger(name: &str) -> Result<Ledger> {
+        let dir = env::temp_dir().join(name);
+        let _ = fs::remove_dir_all(&dir);
+        Ledger::init(2025, dir)
+    }
+
+    fn add(ledger: &mut Ledger, month: u32) -> Result<EntryHash> {
+        let lines = vec![
+            EntryLine::new("5410", Amount::from_minor(100), Side::Debit, None),
+            EntryLine::new("1930", Amount::from_minor(100), Side::Credit, None),
+        ];
+        let date = NaiveDate::from_ymd_opt(2025, month, 1).unwrap();
+        ledger.add_voucher_on_date(date, "A", "Test", lines)
+    }
+
+    #[test]
+    fn resolves_ancestry_suffixes() -> Result<()> {
+        let mut ledger = ledger("bok_ledger_ancestry")?;
+        let origin = ledger.from_ref("HEAD")?;
+        let jan = add(&mut ledger, 1)?;
+        let mar = add(&mut ledger, 3)?;
+        let jun = add(&mut ledger, 6)?;
+
+        let resolve = |r: &str| ledger.from_ref(r).map(|h| h.0);
+        assert_eq!(resolve("HEAD^0")?, jun.0);
+        assert_eq!(resolve("HEAD~")?, mar.0);
+        assert_eq!(resolve("HEAD^^")?, jan.0);
+        assert_eq!(resolve("main~2^")?, origin.0);
+        assert_eq!(resolve("HEAD@{2025-05-31}")?, mar.0);
+        assert_eq!(resolve("@{2025-03-01}~1")?, jan.0);
+        assert_eq!(resolve("HEAD@{2024-12-31}")?, origin.0);
+        assert_eq!(resolve("HEAD@{1}")?, mar.0);
+        assert_eq!(resolve("@{2}~")?, origin.0);
+        assert!(resolve("HEAD~@{1}").is_err());
+        assert!(resolve("HEAD@{4}").is_err());
+        assert!(resolve("HEAD~4").is_err());
+        assert!(resolve("HEAD^2").is_err());
+        fs::remove_dir_all(&ledger.location)?;
+        Ok(())
+    }
+
+    #[test]
+    fn history_follows_previous_entries() -> Result<()> {
+        let mut ledger = ledger("bok_ledger_history")?;
+        let origin = ledger.from_ref("HEAD")?;
+        let jan = add(&mut ledger, 1)?;
+        let mar = add(&mut ledger, 3)?;
+
+        let hashes = ledger
+            .history(&mar)
+            .map(|item| item.map(|(hash, _)| hash.0))
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(hashes, vec![mar.0, jan.0.clone(), origin.0]);
+        let entries = ledger.history(&jan).collect::<Result<Vec<_>>>()?;
+        assert!(matches!(&entries[0].1, Entry::Entry { name, .. } if name == "A1"));
+        assert!(
+            matches!(entries[1].1, Entry::Origin { start, .. } if start == NaiveDate::from_ymd_opt(2025, 1, 1).unwrap())
+        );
+
+        let mut broken = ledger.history(&EntryHash("0".repeat(64)));
+        assert!(matches!(broken.next(), Some(Err(Error::ObjectNotFound(_)))));
+        assert!(broken.next().is_none());
+        fs::remove_dir_all(&ledger.location)?;
+        Ok(())
+    }
+
+    #[test]
+    fn discovers_ledger_in_parent_directories() -> Result<()> {
+        let root = env::temp_dir().join("bok_ledger_discover");
+        let _ = fs::remove_dir_all(&root);
+        let nested = root.join("company").join("invoices");
+        fs::create_dir_all(&nested)?;
+        assert!(matches!(
+            Ledger::discover(&nested),
+            Err(Error::LedgerNotFound(path)) if path == nested
+        ));
+        let location = root.join("company").join(Ledger::DIR_NAME);
+        Ledger::init(2025, location.clone())?;
+        assert_eq!(Ledger::discover(&nested)?, location);
+        assert_eq!(Ledger::discover(&root.join("company"))?, location);
+        assert!(Ledger::discover(&root).is_err());
+        fs::remove_dir_all(&root)?;
+        Ok(())
+    }
+
+    #[test]
+    fn reports_typed_errors() -> Result<()> {
+        let mut ledger = ledger("bok_ledger_errors")?;
+        assert!(matches!(ledger.from_ref("nope"), Err(Error::RefNotFound(r)) if r == "nope"));
+        assert!(matches!(
+            ledger.from_ref("HEAD~"),
+            Err(Error::RefNotFound(_))
+        ));
+        assert!(matches!(
+            ledger.from_ref("HEAD^0x"),
+            Err(Error::InvalidRef(_))
+        ));
+        let date = NaiveDate::from_ymd_opt(2026, 1, 1).unwrap();
+        assert!(matches!(
+            ledger.add_voucher_on_date(date, "A", "Test", vec![]),
+            Err(Error::OutsideFiscalYear { .. })
+        ));
+        let date = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let lines = vec![EntryLine::new(
+            "1930",
+            Amount::from_minor(1),
+            Side::Debit,
+            None,
+        )];
+        assert!(matches!(
+            ledger.add_voucher_on_date(date, "A", "Test", lines),
+            Err(Error::Unbalanced(BalanceError::OneSided(Side::Debit)))
+        ));
+        let missing = ledger.location.join("missing");
+        assert!(matches!(
+            Ledger::from_dir(missing),
+            Err(Error::NotALedger(_))
+        ));
+        fs::remove_dir_all(&ledger.location)?;
+        Ok(())
+    }
+
+    #[test]
+    fn keeps_entries_within_fiscal_year() -> Result<()> {
+        let mut ledger = ledger("bok_ledger_fiscal_year")?;
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let lines = || {
+            vec![
+                EntryLine::new("5410", Amount::from_minor(100), Side::Debit, None),
+                EntryLine::new("1930", Amount::from_minor(100), Side::Credit, None),
+            ]
+        };
+        let fiscal_year = Period::new(Some(date(2025, 1, 1)), Some(date(2025, 12, 31)));
+        for outside in [date(2024, 12, 31), date(2026, 1, 1)] {
+            assert!(matches!(
+                ledger.add_voucher_on_date(outside, "A", "Test", lines()),
+                Err(Error::OutsideFiscalYear { date, fiscal_year: year })
+                    if date == outside && year == fiscal_year
+            ));
+        }
+        assert_eq!(ledger.reflog()?.len(), 1);
+        ledger.add_voucher_on_date(date(2025, 1, 1), "A", "Test", lines())?;
+        ledger.add_voucher_on_date(date(2025, 12, 31), "A", "Test", lines())?;
+        assert_eq!(ledger.next_voucher("A")?, VoucherNumber::new("A", 3));
+        fs::remove_dir_all(&ledger.location)?;
+        Ok(())
+    }
+
+    #[test]
+    fn reverts_entries() -> Result<()> {
+        let mut ledger = ledger("bok_ledger_revert")?;
+        let origin = ledger.from_ref("HEAD")?;
+        let lines = vec![
+            EntryLine::new(
+                "5410",
+                Amount::from_minor(100),
+                Side::Debit,
+                Some("Pennor".to_string()),
+            ),
+            EntryLine::new("1930", Amount::from_minor(100), Side::Credit, None),
+        ];
+        let date = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+        let mistake = ledger.add_voucher_on_date(date, "A", "Fel konto", lines)?;
+        let kept = add(&mut ledger, 4)?;
+        let date = NaiveDate::from_ymd_opt(2025, 5, 1).unwrap();
+        let correction = ledger.revert(&mistake, date, "A")?;
+
+        let entry = ledger.get_entry(&correction)?.clone();
+        assert_eq!(entry.corrects(), Some(mistake.0.as_str()));
+        let Entry::Entry {
+            name,
+            event_date,
+            description,
+            lines,
+            ..
+        } = entry
+        else {
+            panic!("a correction is an entry");
+        };
+        assert_eq!(name, "A3");
+        assert_eq!(event_date, date);
+        assert_eq!(
+            description,
+            format!("Correction of {}: Fel konto", mistake.0)
+        );
+        assert_eq!(
+            lines,
+            vec![
+                EntryLine::new(
+                    "5410",
+                    Amount::from_minor(100),
+                    Side::Credit,
+                    Some("Pennor".to_string()),
+                ),
+                EntryLine::new("1930", Amount::from_minor(100), Side::Debit, None),
+            ]
+        );
+        let balance = ledger.trial_balance(correction.clone(), Period::all())?;
+        assert_eq!(balance.get("5410").unwrap().net(), Amount::from_minor(100));
+
+        assert_eq!(
+            ledger.corrected_by(&mistake)?.map(|h| h.0),
+            Some(correction.0.clone())
+        );
+        assert!(ledger.corrected_by(&kept)?.is_none());
+        assert!(matches!(
+            ledger.revert(&mistake, date, "A"),
+            Err(Error::AlreadyCorrected { hash, by }) if hash == mistake.0 && by == correction.0
+        ));
+        assert!(matches!(
+            ledger.revert(&origin, date, "A"),
+            Err(Error::InvalidInput(_))
+        ));
+
+        let log = ledger.show_log(correction.clone(), &LogFilter::default())?;
+        let marked: Vec<_> = log
+            .lines()
+            .filter(|l| l.contains("[corrected by"))
+            .collect();
+        assert_eq!(
+            marked,
+            [format!(
+                "2025-03-01: A1, Fel konto ({}) [corrected by {}]",
+                &mistake.0[..6],
+                &correction.0[..6]
+            )]
+        );
+        assert!(
+            ledger
+                .show(&mistake)?
+                .ends_with(&format!("\nCorrected by {}\n", correction.0))
+        );
+        assert!(!ledger.show(&correction)?.contains("Corrected by"));
+        fs::remove_dir_all(&ledger.location)?;
+        Ok(())
+    }
+
+    #[test]
+    fn creates_and_checks_out_branches() -> Result<()> {
+        let mut ledger = ledger("bok_ledger_branches")?;
+        let origin = ledger.from_ref("HEAD")?;
+        let jan = add(&mut ledger, 1)?;
+        ledger.create_branch("draft", &origin)?;
+        assert!(matches!(
+            ledger.create_branch("draft", &jan),
+            Err(Error::RefExists(name)) if name == "refs/heads/draft"
+        ));
+        assert!(matches!(
+            ledger.create_branch("bad name", &jan),
+            Err(Error::InvalidRef(_))
+        ));
+        let branches: Vec<_> = ledger
+            .branches()?
+            .into_iter()
+            .map(|(name, hash)| (name, hash.0))
+            .collect();
+        assert_eq!(
+            branches,
+            [
+                ("draft".to_string(), origin.0.clone()),
+                ("main".to_string(), jan.0.clone())
+            ]
+        );
+
+        let head = ledger.checkout("draft")?;
+        assert_eq!(head, Head::Symbolic("refs/heads/draft".to_string()));
+        assert_eq!(head.branch(), Some("draft"));
+        assert_eq!(ledger.head()?, head);
+        let feb = add(&mut ledger, 2)?;
+        assert_eq!(ledger.from_ref("draft")?.0, feb.0);
+        assert_eq!(ledger.from_ref("main")?.0, jan.0);
+
+        assert_eq!(ledger.checkout(&jan.0[..8])?, Head::Detached(jan.0.clone()));
+        let mar = add(&mut ledger, 3)?;
+        assert_eq!(ledger.head()?, Head::Detached(mar.0.clone()));
+        assert_eq!(ledger.from_ref("main")?.0, jan.0);
+        assert_eq!(
+            ledger.reflog()?[1].message,
+            format!("checkout: moving from draft to {}", &jan.0[..8])
+        );
+        fs::remove_dir_all(&ledger.location)?;
+        Ok(())
+    }
+
+    #[test]
+    fn logs_head_movements() -> Result<()> {
+        let mut ledger = ledger("bok_ledger_reflog")?;
+        let origin = ledger.from_ref("HEAD")?;
+        let jan = add(&mut ledger, 1)?;
+        ledger.checkout(&origin.0)?;
+        let reflog = ledger.reflog()?;
+        let moves: Vec<_> = reflog
+            .iter()
+            .map(|e| (e.old.as_str(), e.new.as_str()))
+            .collect();
+        assert_eq!(
+            moves,
+            vec![
+                (jan.0.as_str(), origin.0.as_str()),
+                (origin.0.as_str(), jan.0.as_str()),
+                (NO_HASH, origin.0.as_str()),
+            ]
+        );
+        assert_eq!(reflog[1].message, "record: A1 Test");
+        assert_eq!(
+            reflog[0].message,
+            format!("checkout: moving from main to {}", origin.0)
+        );
+        fs::remove_dir_all(&ledger.location)?;
+        Ok(())
+    }
+
+    #[test]
+    fn moves_flat_objects_only_when_writing() -> Result<()> {
+        let mut ledger = ledger("bok_ledger_flat")?;
+        let jan = add(&mut ledger, 1)?;
+        let objects = ledger.location.join("objects");
+        let flat = objects.join(&jan.0);
+        fs::rename(objects.join(&jan.0[..2]).join(&jan.0[2..]), &flat)?;
+
+        let mut reopened = Ledger::from_dir(ledger.location.clone())?;
+        assert_eq!(reopened.log(&jan, &LogFilter::default())?.len(), 2);
+        assert_eq!(reopened.fsck()?, vec![]);
+        assert!(flat.is_file());
+        add(&mut reopened, 2)?;
+        assert!(!flat.exists());
+        assert_eq!(reopened.from_ref("HEAD~")?.0, jan.0);
+        fs::remove_dir_all(&ledger.location)?;
+        Ok(())
+    }
+
+    #[test]
+    fn refuses_to_fork_when_head_moved() -> Result<()> {
+        let mut first = ledger("bok_ledger_head_moved")?;
+        let mut second = Ledger::from_dir(first.location.clone())?;
+        let moved = add(&mut first, 1)?;
+        assert!(matches!(
+            add(&mut second, 2),
+            Err(Error::HeadMoved { actual, .. }) if actual == moved.0
+        ));
+        assert_eq!(Ledger::from_dir(first.location.clone())?.head_hash, moved.0);
+        assert!(!first.location.join("LOCK").exists());
+        fs::remove_dir_all(&first.location)?;
+        Ok(())
+    }
 }
@@ -1,8 +1,33 @@
+mod amount;
+mod atomic;
+mod balance;
+mod chart;
 mod entry;
+mod error;
+mod fsck;
 mod ledger;
+mod log;
+mod objects;
+mod output;
+mod period;
+mod refs;
+mod report;
+mod sie;
+mod voucher;
+mod year_end;
 #[macro_use]
 pub(crate) mod read;
 pub(crate) mod tee_writer;
 
-pub use entry::{Entry, EntryLine, Side};
-pub use ledger::Ledger;
+pub use amount::{Amount, ParseAmountError};
+pub use balance::{AccountTotals, TrialBalance};
+pub use chart::{Account, AccountClass, ChartOfAccounts};
+pub use entry::{BalanceError, Entry, EntryLine, Side, check_balance};
+pub use error::{Error, Result};
+pub use fsck::FsckProblem;
+pub use ledger::{History, Ledger};
+pub use log::LogFilter;
+pub use period::Period;
+pub use refs::{Head, ReflogEntry};
+pub use report::{Report, ReportKind, ReportRow, ReportSection, ReportValue};
+pub use voucher::{SeriesProblem, VoucherNumber};
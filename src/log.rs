@@ -0,0 +1,129 @@
+use crate::{Amount, Entry, Period, Side};
+
+/// Which entries of a chain to show in the log, every entry by default.
+///
+/// An entry is shown when it matches every filter that is set. The Origin has no date,
+/// lines or text, so it's only shown when no filter other than `limit` is set.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LogFilter {
+    /// Only entries with an event date in the period.
+    pub period: Period,
+    /// Only entries with a line on the account.
+    pub account: Option<String>,
+    /// Only entries whose name or description contains the text, ignoring case.
+    pub grep: Option<String>,
+    /// Only entries with a total of at least this amount.
+    pub min_amount: Option<Amount>,
+    /// Only entries with a total of at most this amount.
+    pub max_amount: Option<Amount>,
+    /// Show at most this many entries, newest first.
+    pub limit: Option<usize>,
+}
+
+impl LogFilter {
+    /// Whether the entry is shown, regardless of the limit.
+    ///
+    /// The total of an entry is the sum of its debit lines, which is the same as the sum of
+    /// its credit lines as entries are balanced.
+    pub fn matches(&self, entry: &Entry) -> bool {
+        let Entry::Entry {
+            event_date,
+            name,
+            description,
+            lines,
+            ..
+        } = entry
+        else {
+            return self.period == Period::all()
+                && self.account.is_none()
+                && self.grep.is_none()
+                && self.min_amount.is_none()
+                && self.max_amount.is_none();
+        };
+        if !self.period.contains(*event_date) {
+            return false;
+        }
+        if let Some(account) = &self.account
+            && !lines.iter().any(|l| &l.account == account)
+        {
+            return false;
+        }
+        if let Some(text) = &self.grep {
+            let text = text.to_lowercase();
+            if !name.to_lowercase().contains(&text) && !description.to_lowercase().contains(&text) {
+                return false;
+            }
+        }
+        let total = lines
+            .iter()
+            .filter(|l| l.side == Side::Debit)
+            .try_fold(Amount::ZERO, |total, l| total.checked_add(l.amount));
+        match total {
+            Some(total) => {
+                self.min_amount.is_none_or(|min| total >= min)
+                    && self.max_amount.is_none_or(|max| total <= max)
+            }
+            // Too large for an amount, so above any maximum.
+            None => self.max_amount.is_none(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, NaiveDate};
+
+    use super::*;
+    use crate::EntryLine;
+
+    fn entry(day: u32, account: &str, amount: i64) -> Entry {
+        Entry::Entry {
+            timestamp: DateTime::from_timestamp(0, 0).unwrap(),
+            event_date: NaiveDate::from_ymd_opt(2025, 3, day).unwrap(),
+            name: "A1".to_string(),
+            description: "Kontorsmaterial".to_string(),
+            lines: vec![
+                EntryLine::new(account, Amount::from_minor(amount), Side::Debit, None),
+                EntryLine::new("1930", Amount::from_minor(amount), Side::Credit, None),
+            ],
+            previous_entry: "0".repeat(64),
+        }
+    }
+
+    #[test]
+    fn matches_every_filter() {
+        let origin = Entry::Origin {
+            timestamp: DateTime::from_timestamp(0, 0).unwrap(),
+            start: NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(),
+            end: NaiveDate::from_ymd_opt(2025, 12, 31).unwrap(),
+            previous_year: None,
+        };
+        let all = LogFilter {
+            limit: Some(1),
+            ..LogFilter::default()
+        };
+        assert!(all.matches(&origin));
+        assert!(all.matches(&entry(1, "5410", 100)));
+
+        let filter = LogFilter {
+            period: Period::new(NaiveDate::from_ymd_opt(2025, 3, 10), None),
+            account: Some("5410".to_string()),
+            grep: Some("KONTOR".to_string()),
+            min_amount: Some(Amount::from_minor(100)),
+            max_amount: Some(Amount::from_minor(200)),
+            limit: None,
+        };
+        assert!(!filter.matches(&origin));
+        assert!(filter.matches(&entry(10, "5410", 100)));
+        assert!(filter.matches(&entry(31, "5410", 200)));
+        assert!(!filter.matches(&entry(9, "5410", 100)));
+        assert!(!filter.matches(&entry(10, "6110", 100)));
+        assert!(!filter.matches(&entry(10, "5410", 99)));
+        assert!(!filter.matches(&entry(10, "5410", 201)));
+        let grep = LogFilter {
+            grep: Some("hyra".to_string()),
+            ..LogFilter::default()
+        };
+        assert!(!grep.matches(&entry(10, "5410", 100)));
+    }
+}
@@ -1,10 +1,32 @@
-use std::{env::current_dir, io::Result, path::PathBuf};
+use std::{
+    env::current_dir,
+    fs::{File, read_to_string},
+    io::stdout,
+    path::PathBuf,
+    process::ExitCode,
+    str::FromStr,
+};
 
-use bok::{EntryLine, Ledger, Side};
-use clap::{Parser, Subcommand};
+use bok::{
+    Account, AccountClass, Amount, ChartOfAccounts, Entry, EntryLine, Error, Head, Ledger,
+    LogFilter, Period, Report, Result, Side, TrialBalance,
+};
+use chrono::{Days, Local, Months, NaiveDate};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 
 #[derive(Parser)]
 struct BokArgs {
+    /// Run as if bok was started in DIR instead of the current directory, i.e. `~/company`.
+    /// The ledger is `.bok` in DIR or the closest parent directory that has one.
+    #[arg(short = 'C', long, global = true, value_name = "DIR")]
+    dir: Option<PathBuf>,
+    /// Ledger directory to use as is, i.e. `~/company/.bok`, instead of looking for `.bok`.
+    #[arg(long, global = true, env = "BOK_DIR", value_name = "DIR")]
+    bok_dir: Option<PathBuf>,
+    /// Output format of show, log, balance and the reports. JSON and CSV have the full hash,
+    /// timestamps and every line, see the README for the schema.
+    #[arg(long, global = true, value_enum, default_value_t = Format::Text)]
+    format: Format,
     #[command(subcommand)]
     command: BokCommand,
 }
@@ -12,67 +34,746 @@ struct BokArgs {
 #[derive(Subcommand)]
 enum BokCommand {
     /// Record a item in the Ledger.
+    ///
+    /// Each line is given as ACCOUNT=AMOUNT with an optional per-line description,
+    /// i.e. `--debit 5410=100:Pens --debit 2641=25 --credit 1930=125`.
     #[command(name = "record", visible_alias = "rec")]
     Rec {
-        debit: u16,
-        credit: u16,
-        amount: usize,
+        /// Debit line, ACCOUNT=AMOUNT[:DESCRIPTION].
+        #[arg(short, long = "debit", value_name = "ACCOUNT=AMOUNT", required = true)]
+        debits: Vec<LineArg>,
+        /// Credit line, ACCOUNT=AMOUNT[:DESCRIPTION].
+        #[arg(short, long = "credit", value_name = "ACCOUNT=AMOUNT", required = true)]
+        credits: Vec<LineArg>,
+        /// Voucher series the entry is numbered in.
+        #[arg(short, long, default_value = "A")]
+        series: String,
+        /// Date of the event, YYYY-MM-DD, `today`, `yesterday` or days/weeks ago like `3d`
+        /// or `2w`. Defaults to today and must be within the fiscal year.
+        #[arg(long, value_parser = parse_date)]
+        date: Option<NaiveDate>,
         description: String,
     },
     /// Show a entry using it's REF.
     ///
     /// A REF can be either the sha of that entry or a symbol reference pointing to a entry, i.e.
-    /// HEAD.
+    /// HEAD, a branch or a tag. It can be followed by `~N` or `^` to step back N or one entries,
+    /// or by `@{YYYY-MM-DD}` for the newest entry on or before a date, i.e. `HEAD~3`.
+    /// `HEAD@{N}` is where HEAD pointed N movements ago, see `bok reflog`.
     Show { r#ref: String },
+    /// Record a correcting entry that reverses the entry at REF.
+    Revert {
+        r#ref: String,
+        /// Voucher series the correction is numbered in.
+        #[arg(short, long, default_value = "A")]
+        series: String,
+        /// Date of the correction, same forms as for record. Defaults to today.
+        #[arg(long, value_parser = parse_date)]
+        date: Option<NaiveDate>,
+    },
+    /// List tags, or tag REF (default HEAD) as NAME, i.e. to mark a closed period.
+    Tag {
+        name: Option<String>,
+        r#ref: Option<String>,
+    },
+    /// List branches, or create branch NAME at REF (default HEAD).
+    Branch {
+        name: Option<String>,
+        r#ref: Option<String>,
+    },
+    /// Switch HEAD to a branch, or detach it at any other REF.
+    Checkout { r#ref: String },
     /// Show the history from a given REF.
-    Log { r#ref: Option<String> },
+    ///
+    /// Filters combine, an entry is shown when it matches all of them, i.e.
+    /// `bok log --account 1930 --since 2025-03-01 -n 5`.
+    Log {
+        r#ref: Option<String>,
+        /// Only entries with an event date on or after this date, same forms as for record.
+        #[arg(long, value_parser = parse_date)]
+        since: Option<NaiveDate>,
+        /// Only entries with an event date on or before this date, same forms as for record.
+        #[arg(long, value_parser = parse_date)]
+        until: Option<NaiveDate>,
+        /// Only entries with a line on this account.
+        #[arg(long)]
+        account: Option<String>,
+        /// Only entries whose name or description contains this text, ignoring case.
+        #[arg(long, value_name = "TEXT")]
+        grep: Option<String>,
+        /// Only entries with a total of at least this amount.
+        #[arg(long, value_name = "AMOUNT")]
+        min_amount: Option<Amount>,
+        /// Only entries with a total of at most this amount.
+        #[arg(long, value_name = "AMOUNT")]
+        max_amount: Option<Amount>,
+        /// Show at most this many entries.
+        #[arg(short = 'n', long = "max-count", value_name = "N")]
+        limit: Option<usize>,
+    },
+    /// Show where HEAD has pointed, newest first, to find entries to check out again.
+    Reflog,
+    /// Show debit, credit and net totals per account (trial balance).
+    Balance {
+        /// REF to sum from, defaults to HEAD.
+        #[arg(long)]
+        at: Option<String>,
+        /// Only include entries with an event date on or after this date, same forms as for
+        /// record.
+        #[arg(long, value_parser = parse_date)]
+        from: Option<NaiveDate>,
+        /// Only include entries with an event date on or before this date, same forms as for
+        /// record.
+        #[arg(long, value_parser = parse_date)]
+        to: Option<NaiveDate>,
+    },
+    /// Financial reports following the BAS account ranges.
+    #[command(subcommand)]
+    Report(ReportCommand),
+    /// Export the books to other formats.
+    #[command(subcommand)]
+    Export(ExportCommand),
+    /// Manage the voucher series entries are numbered in.
+    #[command(subcommand)]
+    Series(SeriesCommand),
+    /// Close the fiscal year at HEAD and open the next one.
+    ///
+    /// The result of the year is booked to 2099 against 8999 on its last day. The next year
+    /// starts with an Origin following the closed year and an opening balance entry with
+    /// the balance sheet accounts 1xxx-2xxx, the result moved to 2091.
+    NewYear {
+        /// Voucher series the closing entry is numbered in.
+        #[arg(short, long, default_value = "A")]
+        series: String,
+    },
+    /// Verify the objects, links and refs of the ledger and that voucher series have no gaps.
+    Fsck,
+    /// Consolidate all objects into a single pack file, i.e. after closing a fiscal year.
+    #[command(visible_alias = "pack")]
+    Gc,
     /// Initialize a book from a new year.
-    Init { year: usize, dir: Option<PathBuf> },
+    Init {
+        year: usize,
+        /// Ledger directory to create, defaults to `.bok` in the working directory.
+        #[arg(value_name = "DIR")]
+        location: Option<PathBuf>,
+        /// Month the fiscal year starts in, i.e. 7 for 2025-07-01 to 2026-06-30.
+        #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..=12))]
+        start_month: u32,
+    },
+    /// Create a new book from another format.
+    #[command(subcommand)]
+    Import(ImportCommand),
+    /// Manage the chart of accounts.
+    #[command(subcommand)]
+    Accounts(AccountsCommand),
+}
+
+#[derive(Subcommand)]
+enum SeriesCommand {
+    /// List the voucher series.
+    List,
+    /// Add a voucher series, i.e. `B` for supplier invoices.
+    Add {
+        series: String,
+        #[arg(default_value = "")]
+        description: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ImportCommand {
+    /// Create a book from a SIE type 4 file.
+    Sie {
+        file: PathBuf,
+        /// Ledger directory to create, defaults to `.bok` in the working directory.
+        #[arg(value_name = "DIR")]
+        location: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ExportCommand {
+    /// Write a SIE type 4 file of the fiscal year.
+    Sie {
+        /// REF to export from, defaults to HEAD.
+        #[arg(long)]
+        at: Option<String>,
+        /// Company name written to #FNAMN.
+        #[arg(long, default_value = "")]
+        company: String,
+        /// File to write to, defaults to stdout.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ReportCommand {
+    /// Income statement (resultaträkning).
+    Resultat(ReportArgs),
+    /// Balance sheet (balansräkning).
+    Balans(ReportArgs),
+}
+
+#[derive(Args)]
+struct ReportArgs {
+    /// REF to report from, defaults to HEAD.
+    #[arg(long)]
+    at: Option<String>,
+    /// Start of the period, same forms as for record. Defaults to the start of the fiscal year.
+    #[arg(long, value_parser = parse_date)]
+    from: Option<NaiveDate>,
+    /// End of the period, same forms as for record. Defaults to the end of the fiscal year.
+    #[arg(long, value_parser = parse_date)]
+    to: Option<NaiveDate>,
+    /// Add a column with the same period of the previous fiscal year.
+    #[arg(long)]
+    compare: bool,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Text,
+    Csv,
+    Json,
+}
+
+#[derive(Subcommand)]
+enum AccountsCommand {
+    /// List all accounts in the chart.
+    List,
+    /// Import a BAS kontoplan, exported as CSV, replacing the current chart.
+    Import { file: PathBuf },
+    /// Add or replace a single account.
+    Add {
+        number: u16,
+        name: String,
+        /// One of asset, liability, equity, income or expense. Guessed from the BAS ranges if
+        /// left out.
+        #[arg(long)]
+        class: Option<String>,
+        #[arg(long)]
+        vat_code: Option<String>,
+    },
+    /// Mark an account as active so it can be used in new entries.
+    Activate { number: u16 },
+    /// Mark an account as inactive so it can't be used in new entries.
+    Deactivate { number: u16 },
+}
+
+/// A single line of a voucher as given on the command line.
+#[derive(Debug, Clone)]
+struct LineArg {
+    account: u16,
+    amount: Amount,
+    description: Option<String>,
+}
+
+impl LineArg {
+    fn into_line(self, side: Side) -> EntryLine {
+        EntryLine::new(
+            &self.account.to_string(),
+            self.amount,
+            side,
+            self.description,
+        )
+    }
+}
+
+impl FromStr for LineArg {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let (account, rest) = s
+            .split_once('=')
+            .ok_or_else(|| format!("expected ACCOUNT=AMOUNT, got '{}'", s))?;
+        let (amount, description) = match rest.split_once(':') {
+            Some((amount, description)) => (amount, Some(description.to_string())),
+            None => (rest, None),
+        };
+        Ok(LineArg {
+            account: account
+                .trim()
+                .parse()
+                .map_err(|e| format!("invalid account '{}': {}", account, e))?,
+            amount: amount
+                .trim()
+                .parse()
+                .map_err(|e| format!("invalid amount '{}': {}", amount, e))?,
+            description,
+        })
+    }
+}
+
+/// Parses a date given as YYYY-MM-DD, `today`, `yesterday` or days/weeks ago like `3d`/`2w`.
+fn parse_date(s: &str) -> std::result::Result<NaiveDate, String> {
+    let today = Local::now().date_naive();
+    let days_ago = match s {
+        "today" => Some(0),
+        "yesterday" => Some(1),
+        _ => match (s.strip_suffix('d'), s.strip_suffix('w')) {
+            (Some(n), _) => n.parse::<u64>().ok(),
+            (_, Some(n)) => n.parse::<u64>().ok().and_then(|n| n.checked_mul(7)),
+            _ => None,
+        },
+    };
+    match days_ago {
+        Some(days) => today
+            .checked_sub_days(Days::new(days))
+            .ok_or_else(|| format!("date out of range: {}", s)),
+        None => s
+            .parse()
+            .map_err(|_| format!("expected YYYY-MM-DD, today, yesterday, Nd or Nw: {}", s)),
+    }
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
 }
 
-fn main() -> Result<()> {
+fn run() -> Result<ExitCode> {
     let args = BokArgs::parse();
 
-    let default_path = current_dir()?.join(".bok");
+    // A new ledger goes in the working directory, an existing one is looked for upwards.
+    let work_dir = match args.dir {
+        Some(dir) => current_dir()?.join(dir).canonicalize()?,
+        None => current_dir()?,
+    };
+    let bok_dir = args.bok_dir.map(|dir| work_dir.join(dir));
+    let new_path = bok_dir
+        .clone()
+        .unwrap_or_else(|| work_dir.join(Ledger::DIR_NAME));
 
-    if let BokCommand::Init { year, dir } = args.command {
-        Ledger::init(year, dir.unwrap_or(default_path))?;
+    if let BokCommand::Init {
+        year,
+        location,
+        start_month,
+    } = args.command
+    {
+        let start = i32::try_from(year)
+            .ok()
+            .and_then(|year| NaiveDate::from_ymd_opt(year, start_month, 1))
+            .ok_or_else(|| Error::InvalidInput(format!("Invalid year: {}", year)))?;
+        let end = start
+            .checked_add_months(Months::new(12))
+            .and_then(|day| day.pred_opt())
+            .ok_or(Error::Overflow)?;
+        Ledger::init_fiscal_year(
+            start,
+            end,
+            location.map_or(new_path, |dir| work_dir.join(dir)),
+        )?;
         println!("Ledger initialized");
-        return Ok(());
+        return Ok(ExitCode::SUCCESS);
+    }
+    if let BokCommand::Import(ImportCommand::Sie { file, location }) = args.command {
+        Ledger::import_sie(
+            File::open(file)?,
+            location.map_or(new_path, |dir| work_dir.join(dir)),
+        )?;
+        println!("Ledger imported");
+        return Ok(ExitCode::SUCCESS);
     }
 
-    let mut ledger = Ledger::from_dir(default_path)?;
+    let format = args.format;
+    let location = match bok_dir {
+        Some(dir) => dir,
+        None => Ledger::discover(&work_dir)?,
+    };
+    let mut ledger = Ledger::from_dir(location)?;
     match args.command {
         BokCommand::Rec {
-            debit: left,
-            credit: right,
-            amount,
+            debits,
+            credits,
+            series,
+            date,
             description,
         } => {
-            let left_str = left.to_string();
-            let right_str = right.to_string();
-            let lines = vec![
-                EntryLine::new(&left_str, amount, Side::Debit, Option::<String>::None),
-                EntryLine::new(&right_str, amount, Side::Credit, Option::<String>::None),
-            ];
-            let entry_ref = ledger.add_entry("A1", &description, lines)?;
+            let lines = debits
+                .into_iter()
+                .map(|l| l.into_line(Side::Debit))
+                .chain(credits.into_iter().map(|l| l.into_line(Side::Credit)))
+                .collect();
+            let date = date.unwrap_or(Local::now().date_naive());
+            let entry_ref = ledger.add_voucher_on_date(date, &series, &description, lines)?;
+            let chart = ledger.chart().clone();
             let entry = ledger.get_entry(&entry_ref)?;
-            println!("{}", entry.show());
+            print!("{}", render_entry(entry, &chart, format)?);
         }
         BokCommand::Show { r#ref: entry_ref } => {
             let hash = ledger.from_ref(&entry_ref)?;
-            let entry = ledger.get_entry(&hash)?;
-            let show = entry.show();
-            print!("{}", show);
+            if let Format::Text = format {
+                print!("{}", ledger.show(&hash)?);
+            } else {
+                let chart = ledger.chart().clone();
+                print!(
+                    "{}",
+                    render_entry(ledger.get_entry(&hash)?, &chart, format)?
+                );
+            }
+        }
+        BokCommand::Revert {
+            r#ref: entry_ref,
+            series,
+            date,
+        } => {
+            let hash = ledger.from_ref(&entry_ref)?;
+            let date = date.unwrap_or(Local::now().date_naive());
+            let correction = ledger.revert(&hash, date, &series)?;
+            let chart = ledger.chart().clone();
+            let entry = ledger.get_entry(&correction)?;
+            print!("{}", render_entry(entry, &chart, format)?);
         }
-        BokCommand::Log { r#ref: start } => {
+        BokCommand::Log {
+            r#ref: start,
+            since,
+            until,
+            account,
+            grep,
+            min_amount,
+            max_amount,
+            limit,
+        } => {
             let hash = ledger.from_ref(&start.unwrap_or("HEAD".to_string()))?;
-            let out = ledger.show_log(hash)?;
-            print!("{}", out);
+            let filter = LogFilter {
+                period: Period::new(since, until),
+                account,
+                grep,
+                min_amount,
+                max_amount,
+                limit,
+            };
+            match format {
+                Format::Text => print!("{}", ledger.show_log(hash, &filter)?),
+                Format::Csv => {
+                    print!("{}", Entry::CSV_HEADER);
+                    for (_, entry) in ledger.log(&hash, &filter)? {
+                        print!("{}", entry.to_csv()?);
+                    }
+                }
+                Format::Json => {
+                    let entries = ledger
+                        .log(&hash, &filter)?
+                        .iter()
+                        .map(|(_, entry)| entry.to_json())
+                        .collect::<Result<Vec<_>>>()?;
+                    println!("[{}]", entries.join(",\n"));
+                }
+            }
+        }
+        BokCommand::Reflog => {
+            for (n, entry) in ledger.reflog()?.iter().enumerate() {
+                println!(
+                    "{} HEAD@{{{}}} {} {}: {}",
+                    short(&entry.new),
+                    n,
+                    entry
+                        .timestamp
+                        .with_timezone(&Local)
+                        .format("%Y-%m-%d %H:%M"),
+                    entry.user,
+                    entry.message
+                );
+            }
+        }
+        BokCommand::Balance { at, from, to } => {
+            let hash = ledger.from_ref(&at.unwrap_or("HEAD".to_string()))?;
+            let balance = ledger.trial_balance(hash, Period::new(from, to))?;
+            match format {
+                Format::Text => print!("{}", show_balance(&balance, ledger.chart())?),
+                Format::Csv => print!("{}", balance.to_csv(ledger.chart())?),
+                Format::Json => print!("{}", balance.to_json(ledger.chart())?),
+            }
+            balance.check()?;
+        }
+        BokCommand::Export(ExportCommand::Sie {
+            at,
+            company,
+            output,
+        }) => {
+            let hash = ledger.from_ref(&at.unwrap_or("HEAD".to_string()))?;
+            let today = Local::now().date_naive();
+            match output {
+                Some(path) => ledger.export_sie(hash, &company, today, File::create(path)?)?,
+                None => ledger.export_sie(hash, &company, today, stdout().lock())?,
+            }
+        }
+        BokCommand::Tag {
+            name: Some(name),
+            r#ref: entry_ref,
+        } => {
+            let hash = ledger.from_ref(entry_ref.as_deref().unwrap_or("HEAD"))?;
+            ledger.create_tag(&name, &hash)?;
+        }
+        BokCommand::Tag { name: None, .. } => {
+            for (name, hash) in ledger.tags()? {
+                println!("{} ({})", name, short(hash.as_ref()));
+            }
         }
-        BokCommand::Init { .. } => {
+        BokCommand::Branch {
+            name: Some(name),
+            r#ref: entry_ref,
+        } => {
+            let hash = ledger.from_ref(entry_ref.as_deref().unwrap_or("HEAD"))?;
+            ledger.create_branch(&name, &hash)?;
+        }
+        BokCommand::Branch { name: None, .. } => {
+            let head = ledger.head()?;
+            for (name, hash) in ledger.branches()? {
+                let current = head.branch() == Some(name.as_str());
+                let marker = if current { '*' } else { ' ' };
+                println!("{} {} ({})", marker, name, short(hash.as_ref()));
+            }
+        }
+        BokCommand::Checkout { r#ref: entry_ref } => match ledger.checkout(&entry_ref)? {
+            Head::Symbolic(branch) => println!("Switched to {}", branch),
+            Head::Detached(hash) => println!("HEAD is now at {}", short(&hash)),
+        },
+        BokCommand::Series(SeriesCommand::List) => {
+            for (series, description) in ledger.series() {
+                println!("{: <4} {}", series, description);
+            }
+        }
+        BokCommand::Series(SeriesCommand::Add {
+            series,
+            description,
+        }) => ledger.add_series(&series, &description)?,
+        BokCommand::Fsck => {
+            let problems = ledger.fsck()?;
+            for problem in &problems {
+                println!("{}", problem);
+            }
+            if !problems.is_empty() {
+                eprintln!("{} problems found", problems.len());
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        BokCommand::NewYear { series } => {
+            let head = ledger.new_year(&series)?;
+            println!("Opened fiscal year {}", ledger.fiscal_year(head.clone())?);
+            let chart = ledger.chart().clone();
+            print!(
+                "{}",
+                render_entry(ledger.get_entry(&head)?, &chart, format)?
+            );
+        }
+        BokCommand::Gc => println!("Packed {} objects", ledger.gc()?),
+        BokCommand::Report(command) => report(&mut ledger, command, format)?,
+        BokCommand::Accounts(command) => accounts(&mut ledger, command)?,
+        BokCommand::Init { .. } | BokCommand::Import(_) => {
             panic!("Shouldn't happen!")
         }
     }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// The first characters of a hash, or all of it if it's shorter, i.e. in a hand-edited ref.
+fn short(hash: &str) -> &str {
+    hash.get(..6).unwrap_or(hash)
+}
+
+/// An entry in the chosen format, the text with account names from the chart.
+fn render_entry(entry: &Entry, chart: &ChartOfAccounts, format: Format) -> Result<String> {
+    Ok(match format {
+        Format::Text => entry.show_with_chart(chart),
+        Format::Csv => format!("{}{}", Entry::CSV_HEADER, entry.to_csv()?),
+        Format::Json => format!("{}\n", entry.to_json()?),
+    })
+}
+
+fn show_balance(balance: &TrialBalance, chart: &ChartOfAccounts) -> Result<String> {
+    let mut result = format!(
+        "{: <40} {:>14} {:>14} {:>14}\n",
+        "account", "debit", "credit", "net"
+    );
+    for (account, totals) in balance.accounts() {
+        let label: String = match chart.name(account) {
+            Some(name) => format!("{} {}", account, name).chars().take(40).collect(),
+            None => account.to_string(),
+        };
+        result += &format!(
+            "{: <40} {:>14} {:>14} {:>14}\n",
+            label,
+            totals.debit,
+            totals.credit,
+            totals.net()
+        );
+    }
+    let totals = balance.totals()?;
+    result += &format!(
+        "{: <40} {:>14} {:>14} {:>14}\n",
+        "total",
+        totals.debit,
+        totals.credit,
+        totals.net()
+    );
+    Ok(result)
+}
+
+fn report(ledger: &mut Ledger, command: ReportCommand, format: Format) -> Result<()> {
+    let (ReportCommand::Resultat(args) | ReportCommand::Balans(args)) = &command;
+    let hash = ledger.from_ref(args.at.as_deref().unwrap_or("HEAD"))?;
+    let fiscal_year = ledger.fiscal_year(hash.clone())?;
+    let mut period = Period::new(args.from.or(fiscal_year.from), args.to.or(fiscal_year.to));
+    if let ReportCommand::Balans(_) = command {
+        // The balance sheet shows the balances at the end of the period.
+        period.from = None;
+    }
+
+    let current = ledger.trial_balance(hash.clone(), period)?;
+    let previous = if args.compare {
+        Some(ledger.comparison(hash, period)?)
+    } else {
+        None
+    };
+    let previous = previous.as_ref().map(|(p, tb)| (*p, tb));
+    let report = match command {
+        ReportCommand::Resultat(_) => {
+            Report::income_statement(ledger.chart(), (period, &current), previous)?
+        }
+        ReportCommand::Balans(_) => {
+            Report::balance_sheet(ledger.chart(), (period, &current), previous)?
+        }
+    };
+    match format {
+        Format::Text => print!("{}", report.to_text()),
+        Format::Csv => print!("{}", report.to_csv()),
+        Format::Json => print!("{}", report.to_json()),
+    }
+    Ok(())
+}
+
+fn accounts(ledger: &mut Ledger, command: AccountsCommand) -> Result<()> {
+    let mut chart = ledger.chart().clone();
+    match command {
+        AccountsCommand::List => {
+            for account in chart.iter() {
+                println!(
+                    "{} {: <50} {: <9} {: <4}{}",
+                    account.number,
+                    account.name,
+                    account.class,
+                    account.vat_code.as_deref().unwrap_or(""),
+                    if account.active { "" } else { " (inactive)" }
+                );
+            }
+            return Ok(());
+        }
+        AccountsCommand::Import { file } => {
+            chart = ChartOfAccounts::from_bas_csv(&read_to_string(file)?)?;
+            println!("Imported {} accounts", chart.len());
+        }
+        AccountsCommand::Add {
+            number,
+            name,
+            class,
+            vat_code,
+        } => {
+            let number = number.to_string();
+            let class = match class {
+                Some(class) => class.parse()?,
+                None => AccountClass::from_bas(&number).ok_or_else(|| {
+                    Error::InvalidInput(format!(
+                        "Can't guess the class of account {}, use --class",
+                        number
+                    ))
+                })?,
+            };
+            chart.insert(Account {
+                number,
+                name,
+                class,
+                vat_code,
+                active: true,
+            });
+        }
+        AccountsCommand::Activate { number } | AccountsCommand::Deactivate { number } => {
+            let active = matches!(command, AccountsCommand::Activate { .. });
+            let account = chart
+                .get_mut(&number.to_string())
+                .ok_or_else(|| Error::UnknownAccount(number.to_string()))?;
+            account.active = active;
+        }
+    }
+    ledger.set_chart(chart)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_line_args() {
+        let line: LineArg = "5410=100.50".parse().unwrap();
+        assert_eq!(
+            line.into_line(Side::Debit),
+            EntryLine::new("5410", Amount::from_minor(10050), Side::Debit, None)
+        );
+        let line: LineArg = "1930 = 125:Betalning: faktura 12".parse().unwrap();
+        assert_eq!(
+            line.into_line(Side::Credit),
+            EntryLine::new(
+                "1930",
+                Amount::from_minor(12500),
+                Side::Credit,
+                Some("Betalning: faktura 12".to_string())
+            )
+        );
+
+        assert!(
+            "5410:100"
+                .parse::<LineArg>()
+                .unwrap_err()
+                .starts_with("expected ACCOUNT=AMOUNT")
+        );
+        assert!(
+            "=100"
+                .parse::<LineArg>()
+                .unwrap_err()
+                .starts_with("invalid account")
+        );
+        assert!(
+            "5410=1,0.0"
+                .parse::<LineArg>()
+                .unwrap_err()
+                .starts_with("invalid amount")
+        );
+    }
+
+    #[test]
+    fn parses_dates() {
+        let today = Local::now().date_naive();
+        let days_ago = |days| today.checked_sub_days(Days::new(days)).unwrap();
+        assert_eq!(parse_date("today"), Ok(today));
+        assert_eq!(parse_date("yesterday"), Ok(days_ago(1)));
+        assert_eq!(parse_date("0d"), Ok(today));
+        assert_eq!(parse_date("3d"), Ok(days_ago(3)));
+        assert_eq!(parse_date("2w"), Ok(days_ago(14)));
+        assert_eq!(
+            parse_date("2025-03-01"),
+            Ok(NaiveDate::from_ymd_opt(2025, 3, 1).unwrap())
+        );
+
+        for bad in [
+            "",
+            "d",
+            "w",
+            "-1d",
+            "3x",
+            "å",
+            "2025-01-0ö",
+            "2025-02-30",
+            "3000000000000000000w",
+        ] {
+            assert!(parse_date(bad).is_err(), "{} should not parse", bad);
+        }
+    }
+}
@@ -0,0 +1,315 @@
+use std::{
+    collections::BTreeMap,
+    fs::{File, create_dir_all, read, read_dir, read_to_string, remove_dir, remove_file, rename},
+    io::{ErrorKind, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+use hex::ToHex;
+use sha2::{Digest, Sha256};
+
+use crate::{Error, Result, atomic::write_atomic};
+
+/// Length of the hash prefix naming the fan-out directory of an object.
+const FAN_OUT: usize = 2;
+
+/// Directory of the pack files under `objects/`.
+const PACK_DIR: &str = "pack";
+
+/// Where a packed object is, as the index of its pack, offset and length.
+#[derive(Debug, Clone, Copy)]
+struct Packed {
+    pack: usize,
+    offset: u64,
+    length: u64,
+}
+
+/// Objects stored by hash under the ledger directory, fanned out on the first two characters
+/// so no directory grows too large, or consolidated into a pack.
+///
+/// A pack holds the stored bytes of its objects back to back, so hashes are unchanged, and
+/// its index has a tab separated line per object with the hash, offset and length.
+/// ```ignore
+/// .bok/objects/ab/cdef...            -> the gzipped entry with hash abcdef...
+/// .bok/objects/pack/pack-<hash>.pack -> objects back to back
+/// .bok/objects/pack/pack-<hash>.idx  -> "<hash>\t<offset>\t<length>" per object
+/// ```
+#[derive(Debug, Clone)]
+pub(crate) struct Objects {
+    location: PathBuf,
+    packs: Vec<PathBuf>,
+    packed: BTreeMap<String, Packed>,
+}
+
+impl Objects {
+    /// Opens the objects at `location`, reading the index of every pack.
+    pub fn open(location: PathBuf) -> Result<Self> {
+        let mut objects = Self {
+            location,
+            packs: vec![],
+            packed: BTreeMap::new(),
+        };
+        let pack_dir = objects.location.join(PACK_DIR);
+        let mut indexes = read_dir_names(&pack_dir)?;
+        indexes.retain(|name| name.ends_with(".idx"));
+        indexes.sort();
+        for index in indexes {
+            let index = pack_dir.join(index);
+            let pack = objects.packs.len();
+            for line in read_to_string(&index)?.lines() {
+                let bad =
+                    || Error::InvalidData(format!("Invalid line in {}: {}", index.display(), line));
+                let fields: Vec<&str> = line.split('\t').collect();
+                let [hash, offset, length] = fields[..] else {
+                    return Err(bad());
+                };
+                let packed = Packed {
+                    pack,
+                    offset: offset.parse().map_err(|_| bad())?,
+                    length: length.parse().map_err(|_| bad())?,
+                };
+                objects.packed.insert(hash.to_string(), packed);
+            }
+            objects.packs.push(index.with_extension("pack"));
+        }
+        Ok(objects)
+    }
+
+    fn path(&self, hash: &str) -> Result<PathBuf> {
+        if hash.len() <= FAN_OUT || !hash.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(Error::ObjectNotFound(hash.to_string()));
+        }
+        let (dir, file) = hash.split_at(FAN_OUT);
+        Ok(self.location.join(dir).join(file))
+    }
+
+    /// Reads the stored bytes of an object, loose, flat or packed.
+    pub fn read(&self, hash: &str) -> Result<Vec<u8>> {
+        for path in [self.path(hash)?, self.location.join(hash)] {
+            match read(path) {
+                Err(e) if e.kind() == ErrorKind::NotFound => {}
+                result => return Ok(result?),
+            }
+        }
+        let Some(packed) = self.packed.get(hash) else {
+            return Err(Error::ObjectNotFound(hash.to_string()));
+        };
+        let mut file = File::open(&self.packs[packed.pack])?;
+        file.seek(SeekFrom::Start(packed.offset))?;
+        let mut content = vec![];
+        file.take(packed.length).read_to_end(&mut content)?;
+        if content.len() as u64 != packed.length {
+            return Err(Error::CorruptObject {
+                hash: hash.to_string(),
+                reason: "pack is truncated".to_string(),
+            });
+        }
+        Ok(content)
+    }
+
+    pub fn write(&self, hash: &str, content: &[u8]) -> Result<()> {
+        let path = self.path(hash)?;
+        if let Some(dir) = path.parent() {
+            create_dir_all(dir)?;
+        }
+        Ok(write_atomic(&path, content)?)
+    }
+
+    /// The hashes of all objects starting with `prefix`, only reading the directory the
+    /// prefix points at when it's long enough.
+    pub fn find(&self, prefix: &str) -> Result<Vec<String>> {
+        if !prefix.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Ok(vec![]);
+        }
+        let dirs = if prefix.len() >= FAN_OUT {
+            vec![prefix[..FAN_OUT].to_string()]
+        } else {
+            let mut dirs = vec![];
+            for dir in read_dir_names(&self.location)? {
+                if dir.len() == FAN_OUT && dir.starts_with(prefix) {
+                    dirs.push(dir);
+                }
+            }
+            dirs
+        };
+        let mut hashes: Vec<String> = self
+            .packed
+            .range(prefix.to_string()..)
+            .map(|(hash, _)| hash)
+            .take_while(|hash| hash.starts_with(prefix))
+            .cloned()
+            .collect();
+        for dir in dirs {
+            for file in read_dir_names(&self.location.join(&dir))? {
+                let hash = format!("{}{}", dir, file);
+                if !file.starts_with('.') && hash.starts_with(prefix) {
+                    hashes.push(hash);
+                }
+            }
+        }
+        hashes.extend(
+            self.flat()?
+                .into_iter()
+                .filter(|hash| hash.starts_with(prefix)),
+        );
+        hashes.sort();
+        hashes.dedup();
+        Ok(hashes)
+    }
+
+    /// Consolidates every object, loose or packed, into a single new pack and removes the
+    /// loose objects and old packs. Returns the number of objects in the pack.
+    pub fn pack(&mut self) -> Result<usize> {
+        let hashes = self.find("")?;
+        if hashes.is_empty() || (self.packs.len() == 1 && hashes.len() == self.packed.len()) {
+            return Ok(hashes.len());
+        }
+        let mut content = vec![];
+        let mut index = String::new();
+        let mut packed = BTreeMap::new();
+        for hash in &hashes {
+            let object = self.read(hash)?;
+            let offset = content.len() as u64;
+            index.push_str(&format!("{}\t{}\t{}\n", hash, offset, object.len()));
+            packed.insert(
+                hash.clone(),
+                Packed {
+                    pack: 0,
+                    offset,
+                    length: object.len() as u64,
+                },
+            );
+            content.extend(object);
+        }
+        let pack_dir = self.location.join(PACK_DIR);
+        create_dir_all(&pack_dir)?;
+        let name: String = Sha256::digest(&content).encode_hex();
+        let pack = pack_dir.join(format!("pack-{}.pack", name));
+        // The index goes last, a pack without an index is never read.
+        write_atomic(&pack, &content)?;
+        write_atomic(&pack.with_extension("idx"), index)?;
+
+        for old in &self.packs {
+            if *old != pack {
+                remove_file(old.with_extension("idx"))?;
+                remove_file(old)?;
+            }
+        }
+        for hash in &hashes {
+            for path in [self.path(hash)?, self.location.join(hash)] {
+                match remove_file(path) {
+                    Err(e) if e.kind() != ErrorKind::NotFound => return Err(e.into()),
+                    _ => {}
+                }
+            }
+        }
+        for dir in read_dir_names(&self.location)? {
+            if dir.len() == FAN_OUT {
+                // Only empty directories are removed, anything written meanwhile stays.
+                let _ = remove_dir(self.location.join(dir));
+            }
+        }
+        self.packs = vec![pack];
+        self.packed = packed;
+        Ok(hashes.len())
+    }
+
+    /// The objects stored directly in `objects/`, as they were before the fan-out.
+    pub fn flat(&self) -> Result<Vec<String>> {
+        Ok(read_dir_names(&self.location)?
+            .into_iter()
+            .filter(|name| name.len() > FAN_OUT && !name.starts_with('.'))
+            .filter(|name| self.location.join(name).is_file())
+            .collect())
+    }
+
+    /// Moves the [`flat`](Self::flat) objects into their fan-out directories.
+    /// Returns the number of objects moved.
+    pub fn migrate(&self) -> Result<usize> {
+        let flat = self.flat()?;
+        for hash in &flat {
+            let path = self.path(hash)?;
+            if let Some(dir) = path.parent() {
+                create_dir_all(dir)?;
+            }
+            rename(self.location.join(hash), path)?;
+        }
+        Ok(flat.len())
+    }
+}
+
+/// The file names in a directory, none if it doesn't exist.
+fn read_dir_names(dir: &Path) -> Result<Vec<String>> {
+    let entries = match read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e.into()),
+    };
+    entries
+        .map(|entry| {
+            entry?
+                .file_name()
+                .into_string()
+                .map_err(|_| Error::InvalidData("Object name isn't valid UTF-8".to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use super::*;
+
+    #[test]
+    fn migrates_flat_objects() -> Result<()> {
+        let dir = env::temp_dir().join("bok_objects");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join("abcdef"), "flat")?;
+        let objects = Objects::open(dir.clone())?;
+        objects.write("abc123", b"fanned")?;
+        objects.write("bbc123", b"other")?;
+
+        assert_eq!(objects.read("abcdef")?, b"flat");
+        assert_eq!(objects.find("ab")?, vec!["abc123", "abcdef"]);
+        assert!(dir.join("abcdef").is_file());
+
+        assert_eq!(objects.migrate()?, 1);
+        assert!(!dir.join("abcdef").exists());
+        assert_eq!(objects.read("abcdef")?, b"flat");
+        assert_eq!(objects.find("ab")?, vec!["abc123", "abcdef"]);
+        assert_eq!(objects.find("abcd")?, vec!["abcdef"]);
+        assert_eq!(objects.find("")?.len(), 3);
+        assert_eq!(objects.find("c")?, Vec::<String>::new());
+        assert_eq!(objects.migrate()?, 0);
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn reads_packed_objects() -> Result<()> {
+        let dir = env::temp_dir().join("bok_objects_pack");
+        let _ = fs::remove_dir_all(&dir);
+        let mut objects = Objects::open(dir.clone())?;
+        objects.write("abc123", b"first")?;
+        objects.write("bbc123", b"second")?;
+        assert_eq!(objects.pack()?, 2);
+        assert!(!dir.join("ab").exists());
+
+        objects.write("abd456", b"loose")?;
+        let mut objects = Objects::open(dir.clone())?;
+        assert_eq!(objects.find("ab")?, vec!["abc123", "abd456"]);
+        assert_eq!(objects.read("bbc123")?, b"second");
+        assert_eq!(objects.read("abd456")?, b"loose");
+        assert!(objects.read("abc124").is_err());
+
+        assert_eq!(objects.pack()?, 3);
+        let objects = Objects::open(dir.clone())?;
+        assert_eq!(objects.read("abd456")?, b"loose");
+        assert_eq!(objects.read("abc123")?, b"first");
+        assert_eq!(fs::read_dir(dir.join(PACK_DIR))?.count(), 2);
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
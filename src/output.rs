@@ -0,0 +1,34 @@
+//! Helpers for the CSV and JSON renderings of entries and reports.
+//!
+//! The JSON is written by hand so the library doesn't depend on serde, amounts are decimal
+//! strings and missing values `null`.
+
+/// Quotes a CSV field if it contains a separator, quote or newline.
+pub(crate) fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// A JSON string literal.
+pub(crate) fn json_str(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// A JSON string literal, or `null`.
+pub(crate) fn json_opt(s: Option<String>) -> String {
+    s.map(|s| json_str(&s)).unwrap_or("null".to_string())
+}
@@ -0,0 +1,41 @@
+use std::fmt::{self, Display};
+
+use chrono::{Months, NaiveDate};
+
+/// An inclusive date range, open ended if either bound is left out.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Period {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+impl Period {
+    /// A period covering every date.
+    pub fn all() -> Self {
+        Self::default()
+    }
+
+    pub fn new(from: Option<NaiveDate>, to: Option<NaiveDate>) -> Self {
+        Self { from, to }
+    }
+
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        self.from.is_none_or(|from| from <= date) && self.to.is_none_or(|to| date <= to)
+    }
+
+    /// The same period one year earlier.
+    pub fn previous_year(&self) -> Self {
+        let shift = |d: NaiveDate| d.checked_sub_months(Months::new(12));
+        Self {
+            from: self.from.and_then(shift),
+            to: self.to.and_then(shift),
+        }
+    }
+}
+
+impl Display for Period {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let date = |d: Option<NaiveDate>| d.map(|d| d.to_string()).unwrap_or_default();
+        f.pad(&format!("{}–{}", date(self.from), date(self.to)))
+    }
+}
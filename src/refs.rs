@@ -0,0 +1,317 @@
+use std::{
+    env,
+    fs::{File, create_dir_all, read_dir, read_to_string},
+    io::{ErrorKind, Write},
+    path::PathBuf,
+};
+
+use chrono::{DateTime, Utc};
+
+use crate::{Error, Result, atomic::write_atomic};
+
+/// Prefix of a symbolic ref in the HEAD file.
+const SYMBOLIC_PREFIX: &str = "ref: ";
+
+pub(crate) const HEADS: &str = "refs/heads";
+pub(crate) const TAGS: &str = "refs/tags";
+
+/// The log of HEAD movements, one line per change.
+const HEAD_LOG: &str = "logs/HEAD";
+
+/// The old hash of the first movement of HEAD, when it didn't point anywhere.
+pub(crate) const NO_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// The branch a new ledger starts on.
+pub(crate) const DEFAULT_BRANCH: &str = "main";
+
+/// What HEAD points at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Head {
+    /// HEAD follows a branch, i.e. `refs/heads/main`, that moves with new entries.
+    Symbolic(String),
+    /// HEAD points directly at an entry.
+    Detached(String),
+}
+
+impl Head {
+    /// The short name of the branch HEAD follows, i.e. `main`, none if it's detached.
+    pub fn branch(&self) -> Option<&str> {
+        match self {
+            Head::Symbolic(name) => name.strip_prefix(HEADS)?.strip_prefix('/'),
+            Head::Detached(_) => None,
+        }
+    }
+}
+
+/// A movement of HEAD recorded in the reflog.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReflogEntry {
+    /// Where HEAD pointed before, [`NO_HASH`] when the ledger was created.
+    pub old: String,
+    pub new: String,
+    pub timestamp: DateTime<Utc>,
+    pub user: String,
+    /// What moved HEAD, i.e. `record: A12 Pens`.
+    pub message: String,
+}
+
+/// Git-style refs stored as files under the ledger directory.
+///
+/// ```ignore
+/// .bok/HEAD              -> "ref: refs/heads/main" or a hash
+/// .bok/refs/heads/main   -> hash
+/// .bok/refs/tags/q1      -> hash
+/// .bok/logs/HEAD         -> "<old> <new> <timestamp> <user>\t<message>" per line
+/// ```
+#[derive(Debug, Clone)]
+pub(crate) struct Refs {
+    location: PathBuf,
+}
+
+impl Refs {
+    pub fn new(location: PathBuf) -> Self {
+        Self { location }
+    }
+
+    pub fn head(&self) -> Result<Head> {
+        let content = read_to_string(self.location.join("HEAD"))?;
+        let content = content.trim();
+        Ok(match content.strip_prefix(SYMBOLIC_PREFIX) {
+            Some(name) => Head::Symbolic(name.to_string()),
+            None => Head::Detached(content.to_string()),
+        })
+    }
+
+    pub fn set_head(&self, head: &Head) -> Result<()> {
+        let content = match head {
+            Head::Symbolic(name) => format!("{}{}", SYMBOLIC_PREFIX, name),
+            Head::Detached(hash) => hash.clone(),
+        };
+        Ok(write_atomic(&self.location.join("HEAD"), content)?)
+    }
+
+    /// The hash HEAD resolves to.
+    pub fn head_hash(&self) -> Result<String> {
+        match self.head()? {
+            Head::Symbolic(name) => self.read(&name)?.ok_or(Error::RefNotFound(name)),
+            Head::Detached(hash) => Ok(hash),
+        }
+    }
+
+    /// Moves HEAD, or the branch it follows, to `hash`.
+    pub fn update_head(&self, hash: &str) -> Result<()> {
+        match self.head()? {
+            Head::Symbolic(name) => self.write(&name, hash),
+            Head::Detached(_) => self.set_head(&Head::Detached(hash.to_string())),
+        }
+    }
+
+    /// Appends a movement of HEAD from `old` to `new` to the reflog.
+    pub fn log_head(&self, old: &str, new: &str, message: &str) -> Result<()> {
+        let path = self.location.join(HEAD_LOG);
+        if let Some(dir) = path.parent() {
+            create_dir_all(dir)?;
+        }
+        let user = env::var("USER")
+            .or_else(|_| env::var("USERNAME"))
+            .unwrap_or_else(|_| "unknown".to_string());
+        let mut file = File::options().create(true).append(true).open(path)?;
+        writeln!(
+            file,
+            "{} {} {} {}\t{}",
+            old,
+            new,
+            Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            user.replace(['\t', '\n'], " "),
+            message.replace(['\t', '\n'], " ")
+        )?;
+        Ok(file.sync_data()?)
+    }
+
+    /// The movements of HEAD, newest first.
+    pub fn head_log(&self) -> Result<Vec<ReflogEntry>> {
+        let content = match read_to_string(self.location.join(HEAD_LOG)) {
+            Ok(content) => content,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e.into()),
+        };
+        let mut entries = content
+            .lines()
+            .map(|line| {
+                let bad = || Error::InvalidData(format!("Invalid reflog line: {}", line));
+                let (fields, message) = line.split_once('\t').ok_or_else(bad)?;
+                let mut fields = fields.splitn(4, ' ');
+                let mut field = || fields.next().ok_or_else(bad);
+                Ok(ReflogEntry {
+                    old: field()?.to_string(),
+                    new: field()?.to_string(),
+                    timestamp: DateTime::parse_from_rfc3339(field()?)
+                        .map_err(|_| bad())?
+                        .to_utc(),
+                    user: field()?.to_string(),
+                    message: message.to_string(),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        entries.reverse();
+        Ok(entries)
+    }
+
+    /// Reads a full ref name like `refs/tags/q1`.
+    pub fn read(&self, name: &str) -> Result<Option<String>> {
+        match read_to_string(self.location.join(name)) {
+            Ok(hash) => Ok(Some(hash.trim().to_string())),
+            Err(e) if matches!(e.kind(), ErrorKind::NotFound | ErrorKind::IsADirectory) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn write(&self, name: &str, hash: &str) -> Result<()> {
+        let path = self.location.join(name);
+        if let Some(dir) = path.parent() {
+            create_dir_all(dir)?;
+        }
+        Ok(write_atomic(&path, hash)?)
+    }
+
+    /// Resolves a short or full ref name, trying it as given and then as a tag and a branch.
+    pub fn resolve(&self, name: &str) -> Result<Option<String>> {
+        if check_name(name).is_err() {
+            return Ok(None);
+        }
+        for full in [
+            name.to_string(),
+            format!("{}/{}", TAGS, name),
+            format!("{}/{}", HEADS, name),
+        ] {
+            if full.starts_with("refs/")
+                && let Some(hash) = self.read(&full)?
+            {
+                return Ok(Some(hash));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Lists the refs under a prefix, i.e. [`HEADS`], as short names and hashes.
+    pub fn list(&self, prefix: &str) -> Result<Vec<(String, String)>> {
+        let mut refs = vec![];
+        let mut dirs = vec![String::new()];
+        while let Some(dir) = dirs.pop() {
+            let path = self.location.join(prefix).join(&dir);
+            let entries = match read_dir(&path) {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            };
+            for entry in entries {
+                let entry = entry?;
+                let file_name = entry
+                    .file_name()
+                    .into_string()
+                    .map_err(|_| Error::InvalidData("Ref name isn't valid UTF-8".to_string()))?;
+                let name = if dir.is_empty() {
+                    file_name
+                } else {
+                    format!("{}/{}", dir, file_name)
+                };
+                if entry.file_type()?.is_dir() {
+                    dirs.push(name);
+                } else {
+                    let hash = read_to_string(entry.path())?.trim().to_string();
+                    refs.push((name, hash));
+                }
+            }
+        }
+        refs.sort();
+        Ok(refs)
+    }
+}
+
+/// Checks that a ref name is a safe relative path without special meaning.
+pub(crate) fn check_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name != "HEAD"
+        && !name
+            .contains(|c: char| c.is_whitespace() || c.is_control() || "~^:?*[\\@{".contains(c))
+        && name
+            .split('/')
+            .all(|part| !part.is_empty() && !part.starts_with('.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::InvalidRef(name.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    fn refs(name: &str) -> Refs {
+        let dir = env::temp_dir().join(name);
+        let _ = fs::remove_dir_all(&dir);
+        Refs::new(dir)
+    }
+
+    #[test]
+    fn resolves_full_names_then_tags_then_branches() -> Result<()> {
+        let refs = refs("bok_refs_resolve");
+        refs.write("refs/tags/q1", "tag")?;
+        refs.write("refs/heads/q1", "branch")?;
+        refs.write("refs/heads/main", "main")?;
+        refs.write("refs/tags/refs/heads/main", "odd tag")?;
+
+        assert_eq!(refs.resolve("q1")?.as_deref(), Some("tag"));
+        assert_eq!(refs.resolve("refs/heads/q1")?.as_deref(), Some("branch"));
+        assert_eq!(refs.resolve("main")?.as_deref(), Some("main"));
+        assert_eq!(refs.resolve("refs/heads/main")?.as_deref(), Some("main"));
+        assert_eq!(refs.resolve("heads/main")?, None);
+        assert_eq!(refs.resolve("q2")?, None);
+        assert_eq!(refs.resolve("q1~1")?, None);
+        fs::remove_dir_all(&refs.location)?;
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_unsafe_names() {
+        for name in ["q1", "2025/q1", "release-1.0", "Å"] {
+            assert!(check_name(name).is_ok(), "{} should be valid", name);
+        }
+        for name in [
+            "", "HEAD", "a b", "a\tb", "a~1", "a^", "a:b", "a?", "a*", "a[1]", "a\\b", "a@{1}",
+            "/a", "a/", "a//b", ".hidden", "a/.b", "../a",
+        ] {
+            assert!(
+                matches!(check_name(name), Err(Error::InvalidRef(n)) if n == name),
+                "{:?} should be invalid",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn lists_nested_refs() -> Result<()> {
+        let refs = refs("bok_refs_list");
+        refs.write("refs/tags/2025/q2", "b")?;
+        refs.write("refs/tags/2025/q1", "a")?;
+        refs.write("refs/tags/closed", "c")?;
+        assert_eq!(
+            refs.list(TAGS)?,
+            [("2025/q1", "a"), ("2025/q2", "b"), ("closed", "c")]
+                .map(|(name, hash)| (name.to_string(), hash.to_string()))
+        );
+        assert_eq!(refs.list(HEADS)?, vec![]);
+        fs::remove_dir_all(&refs.location)?;
+        Ok(())
+    }
+
+    #[test]
+    fn names_the_branch_of_head() {
+        let head = Head::Symbolic(format!("{}/{}", HEADS, "feature/x"));
+        assert_eq!(head.branch(), Some("feature/x"));
+        assert_eq!(Head::Detached("abc".to_string()).branch(), None);
+    }
+}
@@ -0,0 +1,621 @@
+use std::ops::RangeInclusive;
+
+use crate::{
+    AccountClass, Amount, ChartOfAccounts, Error, Ledger, Period, Result, TrialBalance,
+    ledger::EntryHash,
+    output::{csv_field, json_opt, json_str},
+    year_end::RESULT,
+};
+
+/// Sections of the income statement (resultaträkning) by BAS account range.
+const INCOME_STATEMENT: &[(&str, RangeInclusive<u16>)] = &[
+    ("Nettoomsättning", 3000..=3799),
+    ("Övriga rörelseintäkter", 3800..=3999),
+    ("Råvaror och förnödenheter", 4000..=4999),
+    ("Övriga externa kostnader", 5000..=6999),
+    ("Personalkostnader", 7000..=7699),
+    ("Av- och nedskrivningar", 7700..=7899),
+    ("Övriga rörelsekostnader", 7900..=7999),
+    ("Finansiella poster", 8000..=8799),
+    ("Bokslutsdispositioner", 8800..=8899),
+    ("Skatt och årets resultat", 8900..=8999),
+];
+
+/// Asset sections of the balance sheet (balansräkning) by BAS account range.
+const ASSETS: &[(&str, RangeInclusive<u16>)] = &[
+    ("Anläggningstillgångar", 1000..=1399),
+    ("Omsättningstillgångar", 1400..=1999),
+];
+
+/// Equity and liability sections of the balance sheet by BAS account range.
+const EQUITY_AND_LIABILITIES: &[(&str, RangeInclusive<u16>)] = &[
+    ("Eget kapital", 2000..=2099),
+    ("Obeskattade reserver", 2100..=2199),
+    ("Avsättningar", 2200..=2299),
+    ("Långfristiga skulder", 2300..=2399),
+    ("Kortfristiga skulder", 2400..=2999),
+];
+
+/// Title of the section for accounts outside the BAS ranges.
+const OTHER: &str = "Övrigt";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportKind {
+    /// Resultaträkning.
+    IncomeStatement,
+    /// Balansräkning.
+    BalanceSheet,
+}
+
+impl ReportKind {
+    pub fn title(&self) -> &'static str {
+        match self {
+            ReportKind::IncomeStatement => "Resultaträkning",
+            ReportKind::BalanceSheet => "Balansräkning",
+        }
+    }
+}
+
+/// An amount for the reported period, and for the comparison period if there is one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReportValue {
+    pub amount: Amount,
+    pub previous: Option<Amount>,
+}
+
+impl ReportValue {
+    fn add(&mut self, other: ReportValue) -> Result<()> {
+        self.amount = self
+            .amount
+            .checked_add(other.amount)
+            .ok_or(Error::Overflow)?;
+        self.previous = match (self.previous, other.previous) {
+            (Some(a), Some(b)) => Some(a.checked_add(b).ok_or(Error::Overflow)?),
+            (a, b) => a.or(b),
+        };
+        Ok(())
+    }
+
+    fn is_zero(&self) -> bool {
+        self.amount.is_zero() && self.previous.is_none_or(Amount::is_zero)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReportRow {
+    /// The account number, `None` for computed rows.
+    pub account: Option<String>,
+    pub name: String,
+    pub value: ReportValue,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReportSection {
+    pub title: String,
+    pub rows: Vec<ReportRow>,
+    pub total: ReportValue,
+}
+
+/// An income statement or balance sheet built from trial balances.
+///
+/// Income and liabilities are shown as positive amounts, i.e. the income statement shows
+/// revenue as positive and costs as negative.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    pub kind: ReportKind,
+    pub period: Period,
+    pub comparison: Option<Period>,
+    pub sections: Vec<ReportSection>,
+    pub totals: Vec<(String, ReportValue)>,
+}
+
+impl Report {
+    /// Builds the income statement from the trial balance of the period, and optionally the
+    /// trial balance of the comparison period.
+    ///
+    /// The closing entry of a fiscal year moves its result to the balance sheet through 8999,
+    /// so 8999 is left out to show the result of a closed year.
+    pub fn income_statement(
+        chart: &ChartOfAccounts,
+        period: (Period, &TrialBalance),
+        comparison: Option<(Period, &TrialBalance)>,
+    ) -> Result<Self> {
+        let builder = Builder::new(chart, period.1, comparison.map(|c| c.1));
+        let sections = builder.sections(INCOME_STATEMENT, true, |account, class| {
+            account != RESULT && matches!(class, AccountClass::Income | AccountClass::Expense)
+        })?;
+        let result = total(&sections)?;
+        Ok(Report {
+            kind: ReportKind::IncomeStatement,
+            period: period.0,
+            comparison: comparison.map(|c| c.0),
+            sections,
+            totals: vec![("Årets resultat".to_string(), result)],
+        })
+    }
+
+    /// Builds the balance sheet from the trial balance up to the end of the period, and
+    /// optionally the trial balance up to the end of the comparison period.
+    ///
+    /// The result of the period that hasn't been booked to equity yet is included as
+    /// "Beräknat resultat" so the two sides balance.
+    pub fn balance_sheet(
+        chart: &ChartOfAccounts,
+        period: (Period, &TrialBalance),
+        comparison: Option<(Period, &TrialBalance)>,
+    ) -> Result<Self> {
+        let builder = Builder::new(chart, period.1, comparison.map(|c| c.1));
+        let mut assets = builder.sections(ASSETS, false, |_, c| c == AccountClass::Asset)?;
+        let mut equity = builder.sections(EQUITY_AND_LIABILITIES, true, |_, c| {
+            matches!(c, AccountClass::Equity | AccountClass::Liability)
+        })?;
+        let result = total(&builder.sections(INCOME_STATEMENT, true, |_, class| {
+            matches!(class, AccountClass::Income | AccountClass::Expense)
+        })?)?;
+        if !result.is_zero() {
+            equity.push(ReportSection {
+                title: "Årets resultat".to_string(),
+                rows: vec![ReportRow {
+                    account: None,
+                    name: "Beräknat resultat".to_string(),
+                    value: result,
+                }],
+                total: result,
+            });
+        }
+        let total_assets = total(&assets)?;
+        let total_equity = total(&equity)?;
+        assets.append(&mut equity);
+        Ok(Report {
+            kind: ReportKind::BalanceSheet,
+            period: period.0,
+            comparison: comparison.map(|c| c.0),
+            sections: assets,
+            totals: vec![
+                ("Summa tillgångar".to_string(), total_assets),
+                ("Summa eget kapital och skulder".to_string(), total_equity),
+            ],
+        })
+    }
+
+    /// Plain text rendering of the report.
+    pub fn to_text(&self) -> String {
+        let row = |label: &str, value: &ReportValue| {
+            let mut line = format!("{: <50} {:>22}", label, value.amount);
+            if self.comparison.is_some() {
+                line += &format!(" {:>22}", value.previous.unwrap_or_default());
+            }
+            line + "\n"
+        };
+
+        let mut result = format!("{: <50} {:>22}", self.kind.title(), self.period);
+        if let Some(comparison) = &self.comparison {
+            result += &format!(" {:>22}", comparison);
+        }
+        result.push('\n');
+        for section in &self.sections {
+            result.push('\n');
+            result += &format!("{}\n", section.title);
+            for r in &section.rows {
+                let label = match &r.account {
+                    Some(account) => format!("  {} {}", account, r.name),
+                    None => format!("  {}", r.name),
+                };
+                result += &row(&label.chars().take(50).collect::<String>(), &r.value);
+            }
+            result += &row(
+                &format!("Summa {}", section.title.to_lowercase()),
+                &section.total,
+            );
+        }
+        result.push('\n');
+        for (label, value) in &self.totals {
+            result += &row(label, value);
+        }
+        result
+    }
+
+    /// CSV rendering with the columns `section,account,name,amount,previous`.
+    ///
+    /// Section totals and report totals are rows without an account.
+    pub fn to_csv(&self) -> String {
+        let row = |section: &str, account: &str, name: &str, value: &ReportValue| {
+            format!(
+                "{},{},{},{},{}\n",
+                csv_field(section),
+                csv_field(account),
+                csv_field(name),
+                value.amount,
+                value.previous.map(|p| p.to_string()).unwrap_or_default()
+            )
+        };
+        let mut result = "section,account,name,amount,previous\n".to_string();
+        for section in &self.sections {
+            for r in &section.rows {
+                let account = r.account.as_deref().unwrap_or("");
+                result += &row(&section.title, account, &r.name, &r.value);
+            }
+            let label = format!("Summa {}", section.title.to_lowercase());
+            result += &row(&section.title, "", &label, &section.total);
+        }
+        for (label, value) in &self.totals {
+            result += &row("", "", label, value);
+        }
+        result
+    }
+
+    /// JSON rendering of the report, amounts are decimal strings.
+    pub fn to_json(&self) -> String {
+        let period = |p: &Period| {
+            format!(
+                "{{\"from\":{},\"to\":{}}}",
+                json_opt(p.from.map(|d| d.to_string())),
+                json_opt(p.to.map(|d| d.to_string()))
+            )
+        };
+        let value = |v: &ReportValue| {
+            format!(
+                "\"amount\":{},\"previous\":{}",
+                json_str(&v.amount.to_string()),
+                json_opt(v.previous.map(|p| p.to_string()))
+            )
+        };
+        let sections: Vec<String> = self
+            .sections
+            .iter()
+            .map(|s| {
+                let rows: Vec<String> = s
+                    .rows
+                    .iter()
+                    .map(|r| {
+                        format!(
+                            "{{\"account\":{},\"name\":{},{}}}",
+                            json_opt(r.account.clone()),
+                            json_str(&r.name),
+                            value(&r.value)
+                        )
+                    })
+                    .collect();
+                format!(
+                    "{{\"title\":{},\"rows\":[{}],\"total\":{{{}}}}}",
+                    json_str(&s.title),
+                    rows.join(","),
+                    value(&s.total)
+                )
+            })
+            .collect();
+        let totals: Vec<String> = self
+            .totals
+            .iter()
+            .map(|(label, v)| format!("{{\"label\":{},{}}}", json_str(label), value(v)))
+            .collect();
+        format!(
+            "{{\"report\":{},\"period\":{},\"comparison\":{},\"sections\":[{}],\"totals\":[{}]}}\n",
+            json_str(self.kind.title()),
+            period(&self.period),
+            self.comparison
+                .as_ref()
+                .map(period)
+                .unwrap_or("null".to_string()),
+            sections.join(","),
+            totals.join(",")
+        )
+    }
+}
+
+struct Builder<'a> {
+    chart: &'a ChartOfAccounts,
+    current: &'a TrialBalance,
+    previous: Option<&'a TrialBalance>,
+}
+
+impl<'a> Builder<'a> {
+    fn new(
+        chart: &'a ChartOfAccounts,
+        current: &'a TrialBalance,
+        previous: Option<&'a TrialBalance>,
+    ) -> Self {
+        Self {
+            chart,
+            current,
+            previous,
+        }
+    }
+
+    fn class(&self, account: &str) -> Option<AccountClass> {
+        self.chart
+            .get(account)
+            .map(|a| a.class)
+            .or_else(|| AccountClass::from_bas(account))
+    }
+
+    /// Groups the matching accounts, by number and class, into sections, adding an "Övrigt"
+    /// section for accounts outside the ranges. `credit_positive` flips the sign of the net
+    /// balance.
+    fn sections(
+        &self,
+        ranges: &[(&str, RangeInclusive<u16>)],
+        credit_positive: bool,
+        include: impl Fn(&str, AccountClass) -> bool,
+    ) -> Result<Vec<ReportSection>> {
+        let mut accounts: Vec<&str> = self.current.accounts().map(|(a, _)| a).collect();
+        if let Some(previous) = self.previous {
+            accounts.extend(previous.accounts().map(|(a, _)| a));
+        }
+        accounts.sort();
+        accounts.dedup();
+
+        let value = |balance: &TrialBalance, account: &str| {
+            let net = balance.get(account).map(|t| t.net()).unwrap_or_default();
+            if credit_positive {
+                net.checked_neg().expect("totals are kept within range")
+            } else {
+                net
+            }
+        };
+        let mut sections: Vec<ReportSection> = ranges
+            .iter()
+            .map(|(title, _)| title)
+            .chain([&OTHER])
+            .map(|title| ReportSection {
+                title: title.to_string(),
+                rows: vec![],
+                total: ReportValue {
+                    amount: Amount::ZERO,
+                    previous: self.previous.map(|_| Amount::ZERO),
+                },
+            })
+            .collect();
+        for account in accounts {
+            if !self
+                .class(account)
+                .is_some_and(|class| include(account, class))
+            {
+                continue;
+            }
+            let row_value = ReportValue {
+                amount: value(self.current, account),
+                previous: self.previous.map(|p| value(p, account)),
+            };
+            if row_value.is_zero() {
+                continue;
+            }
+            let number = account.parse::<u16>().ok();
+            let index = ranges
+                .iter()
+                .position(|(_, range)| number.is_some_and(|n| range.contains(&n)))
+                .unwrap_or(ranges.len());
+            let section = &mut sections[index];
+            section.total.add(row_value)?;
+            section.rows.push(ReportRow {
+                account: Some(account.to_string()),
+                name: self.chart.name(account).unwrap_or_default().to_string(),
+                value: row_value,
+            });
+        }
+        sections.retain(|s| !s.rows.is_empty());
+        Ok(sections)
+    }
+}
+
+impl Ledger {
+    /// The same period in the previous fiscal year and its trial balance, for the comparison
+    /// column of the reports.
+    ///
+    /// The previous fiscal year is the chain the Origin of `hash` follows. A bound of
+    /// `period` on the first or last day of the fiscal year moves to the first or last day of
+    /// the previous one, any other date moves back a year. The first fiscal year of a ledger
+    /// is compared against an empty trial balance.
+    pub fn comparison(&self, hash: EntryHash, period: Period) -> Result<(Period, TrialBalance)> {
+        let Some(previous) = self.previous_year(&hash)? else {
+            return Ok((period.previous_year(), TrialBalance::new()));
+        };
+        let fiscal_year = self.fiscal_year(hash)?;
+        let previous_year = self.fiscal_year(previous.clone())?;
+        let shifted = period.previous_year();
+        let move_bound = |bound, shifted| {
+            if bound == fiscal_year.from {
+                previous_year.from
+            } else if bound == fiscal_year.to {
+                previous_year.to
+            } else {
+                shifted
+            }
+        };
+        let period = Period::new(
+            move_bound(period.from, shifted.from),
+            move_bound(period.to, shifted.to),
+        );
+        Ok((period, self.trial_balance(previous, period)?))
+    }
+}
+
+fn total(sections: &[ReportSection]) -> Result<ReportValue> {
+    let mut total = ReportValue {
+        amount: Amount::ZERO,
+        previous: None,
+    };
+    for section in sections {
+        total.add(section.total)?;
+    }
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::{EntryLine, Side};
+
+    fn balance(lines: &[(&str, i64, Side)]) -> TrialBalance {
+        let mut balance = TrialBalance::new();
+        for (account, amount, side) in lines {
+            let line = EntryLine::new(account, Amount::from_minor(*amount), *side, None);
+            balance.add_line(&line).unwrap();
+        }
+        balance
+    }
+
+    #[test]
+    fn balance_sheet_balances_with_unbooked_result() -> Result<()> {
+        let tb = balance(&[
+            ("1930", 10000, Side::Debit),
+            ("2081", 10000, Side::Credit),
+            ("1930", 500, Side::Debit),
+            ("3001", 500, Side::Credit),
+            ("5410", 200, Side::Debit),
+            ("1930", 200, Side::Credit),
+        ]);
+        let chart = ChartOfAccounts::new();
+        let report = Report::balance_sheet(&chart, (Period::all(), &tb), None)?;
+        assert_eq!(report.totals[0].1, report.totals[1].1);
+        assert_eq!(report.totals[0].1.amount, Amount::from_minor(10300));
+
+        let report = Report::income_statement(&chart, (Period::all(), &tb), None)?;
+        assert_eq!(report.totals[0].1.amount, Amount::from_minor(300));
+        Ok(())
+    }
+
+    #[test]
+    fn comparison_column_is_filled() -> Result<()> {
+        let current = balance(&[("3001", 500, Side::Credit), ("1930", 500, Side::Debit)]);
+        let previous = balance(&[("3001", 300, Side::Credit), ("1930", 300, Side::Debit)]);
+        let chart = ChartOfAccounts::new();
+        let report = Report::income_statement(
+            &chart,
+            (Period::all(), &current),
+            Some((Period::all(), &previous)),
+        )?;
+        assert_eq!(
+            report.totals[0].1,
+            ReportValue {
+                amount: Amount::from_minor(500),
+                previous: Some(Amount::from_minor(300)),
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn compares_against_previous_fiscal_year() -> Result<()> {
+        let dir = env::temp_dir().join("bok_report_comparison");
+        let _ = fs::remove_dir_all(&dir);
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d);
+        let lines = |debit, credit, amount| {
+            vec![
+                EntryLine::new(debit, Amount::from_minor(amount), Side::Debit, None),
+                EntryLine::new(credit, Amount::from_minor(amount), Side::Credit, None),
+            ]
+        };
+        let mut ledger = Ledger::init(2025, dir.clone())?;
+        let sale = lines("1930", "3001", 100000);
+        ledger.add_voucher_on_date(date(2025, 3, 1).unwrap(), "A", "Försäljning", sale)?;
+        let head = ledger.from_ref("HEAD")?;
+        let (period, balance) = ledger.comparison(head, Period::new(date(2025, 1, 1), None))?;
+        assert_eq!(period, Period::new(date(2024, 1, 1), None));
+        assert_eq!(balance, TrialBalance::new());
+
+        ledger.new_year("A")?;
+        let sale = lines("1930", "3001", 20000);
+        ledger.add_voucher_on_date(date(2026, 2, 1).unwrap(), "A", "Försäljning", sale)?;
+        let head = ledger.from_ref("HEAD")?;
+        let fiscal_year = ledger.fiscal_year(head.clone())?;
+        let (period, balance) = ledger.comparison(head.clone(), fiscal_year)?;
+        assert_eq!(period, Period::new(date(2025, 1, 1), date(2025, 12, 31)));
+        assert_eq!(
+            balance.get("3001").unwrap().net(),
+            Amount::from_minor(-100000)
+        );
+
+        let (period, balance) = ledger.comparison(head, Period::new(None, date(2026, 3, 31)))?;
+        assert_eq!(period, Period::new(None, date(2025, 3, 31)));
+        assert_eq!(
+            balance.get("1930").unwrap().net(),
+            Amount::from_minor(100000)
+        );
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn shows_result_of_closed_year() -> Result<()> {
+        let dir = env::temp_dir().join("bok_report_closed_year");
+        let _ = fs::remove_dir_all(&dir);
+        let date = |m| NaiveDate::from_ymd_opt(2025, m, 1).unwrap();
+        let lines = |debit, credit, amount| {
+            vec![
+                EntryLine::new(debit, Amount::from_minor(amount), Side::Debit, None),
+                EntryLine::new(credit, Amount::from_minor(amount), Side::Credit, None),
+            ]
+        };
+        let mut ledger = Ledger::init(2025, dir.clone())?;
+        ledger.add_voucher_on_date(date(3), "A", "Försäljning", lines("1930", "3001", 100000))?;
+        ledger.add_voucher_on_date(date(4), "A", "Pennor", lines("5410", "1930", 30000))?;
+        let head = ledger.new_year("A")?;
+        let profit = Amount::from_minor(70000);
+
+        let closed = ledger.previous_year(&head)?.unwrap();
+        let balance = ledger.trial_balance(closed, Period::all())?;
+        let report = Report::income_statement(ledger.chart(), (Period::all(), &balance), None)?;
+        assert_eq!(report.totals[0].1.amount, profit);
+        assert!(
+            report
+                .sections
+                .iter()
+                .flat_map(|s| &s.rows)
+                .all(|r| r.account.as_deref() != Some(RESULT))
+        );
+        let report = Report::balance_sheet(ledger.chart(), (Period::all(), &balance), None)?;
+        assert_eq!(report.totals[0].1, report.totals[1].1);
+
+        let fiscal_year = ledger.fiscal_year(head.clone())?;
+        let current = ledger.trial_balance(head.clone(), fiscal_year)?;
+        let (period, previous) = ledger.comparison(head, fiscal_year)?;
+        let report = Report::income_statement(
+            ledger.chart(),
+            (fiscal_year, &current),
+            Some((period, &previous)),
+        )?;
+        assert_eq!(report.totals[0].1.previous, Some(profit));
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn compares_broken_fiscal_years() -> Result<()> {
+        let dir = env::temp_dir().join("bok_report_broken_comparison");
+        let _ = fs::remove_dir_all(&dir);
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d);
+        let lines = vec![
+            EntryLine::new("1930", Amount::from_minor(100000), Side::Debit, None),
+            EntryLine::new("3001", Amount::from_minor(100000), Side::Credit, None),
+        ];
+        let start = date(2025, 7, 1).unwrap();
+        let mut ledger = Ledger::init_fiscal_year(start, date(2026, 6, 30).unwrap(), dir.clone())?;
+        ledger.add_voucher_on_date(date(2026, 2, 1).unwrap(), "A", "Försäljning", lines)?;
+        let head = ledger.new_year("A")?;
+
+        let fiscal_year = ledger.fiscal_year(head.clone())?;
+        assert_eq!(
+            fiscal_year,
+            Period::new(date(2026, 7, 1), date(2027, 6, 30))
+        );
+        let (period, balance) = ledger.comparison(head.clone(), fiscal_year)?;
+        assert_eq!(period, Period::new(date(2025, 7, 1), date(2026, 6, 30)));
+        assert_eq!(
+            balance.get("3001").unwrap().net(),
+            Amount::from_minor(-100000)
+        );
+
+        // A part of the year compares against the same months of the previous one.
+        let (period, balance) =
+            ledger.comparison(head, Period::new(date(2026, 7, 1), date(2027, 1, 31)))?;
+        assert_eq!(period, Period::new(date(2025, 7, 1), date(2026, 1, 31)));
+        assert_eq!(balance.get("3001"), None);
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,56 @@
+//! The PC8 (code page 437) encoding required by SIE files.
+
+/// Characters 0x80-0xFF of code page 437.
+const HIGH: [char; 128] = [
+    'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç', 'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å', //
+    'É', 'æ', 'Æ', 'ô', 'ö', 'ò', 'û', 'ù', 'ÿ', 'Ö', 'Ü', '¢', '£', '¥', '₧', 'ƒ', //
+    'á', 'í', 'ó', 'ú', 'ñ', 'Ñ', 'ª', 'º', '¿', '⌐', '¬', '½', '¼', '¡', '«', '»', //
+    '░', '▒', '▓', '│', '┤', '╡', '╢', '╖', '╕', '╣', '║', '╗', '╝', '╜', '╛', '┐', //
+    '└', '┴', '┬', '├', '─', '┼', '╞', '╟', '╚', '╔', '╩', '╦', '╠', '═', '╬', '╧', //
+    '╨', '╤', '╥', '╙', '╘', '╒', '╓', '╫', '╪', '┘', '┌', '█', '▄', '▌', '▐', '▀', //
+    'α', 'ß', 'Γ', 'π', 'Σ', 'σ', 'µ', 'τ', 'Φ', 'Θ', 'Ω', 'δ', '∞', 'φ', 'ε', '∩', //
+    '≡', '±', '≥', '≤', '⌠', '⌡', '÷', '≈', '°', '∙', '·', '√', 'ⁿ', '²', '■', '\u{a0}',
+];
+
+/// Encodes a string as code page 437, replacing characters without a mapping with `?`.
+pub fn encode(s: &str) -> Vec<u8> {
+    s.chars()
+        .map(|c| match c {
+            c if c.is_ascii() => c as u8,
+            c => HIGH
+                .iter()
+                .position(|h| *h == c)
+                .map(|i| 0x80 + i as u8)
+                .unwrap_or(b'?'),
+        })
+        .collect()
+}
+
+/// Decodes code page 437 bytes into a string.
+pub fn decode(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|b| match b {
+            0x00..=0x7f => *b as char,
+            _ => HIGH[(b - 0x80) as usize],
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_swedish_letters() {
+        assert_eq!(encode("åäöÅÄÖ"), [0x86, 0x84, 0x94, 0x8f, 0x8e, 0x99]);
+        assert_eq!(encode("Moms 25 %"), b"Moms 25 %");
+        assert_eq!(encode("€"), b"?");
+    }
+
+    #[test]
+    fn decodes_what_it_encodes() {
+        let text = "Företagskonto, Årets resultat, Ingående moms";
+        assert_eq!(decode(&encode(text)), text);
+    }
+}
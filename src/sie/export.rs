@@ -0,0 +1,201 @@
+use std::{collections::BTreeMap, io::Write};
+
+use chrono::NaiveDate;
+
+use super::{cp437, field, quote};
+use crate::{
+    AccountClass, Entry, Ledger, Result, Side, TrialBalance, ledger::EntryHash,
+    voucher::split_voucher_name,
+};
+
+impl Ledger {
+    /// Writes the chain from `hash` back to its Origin as a SIE type 4 file.
+    ///
+    /// The fiscal year of the Origin becomes `#RAR 0`, the opening balance entry becomes
+    /// `#IB` rows and every other entry a `#VER` with one `#TRANS` per line, debits positive.
+    /// The balances at `hash` are written as `#UB` rows for the balance sheet accounts and
+    /// `#RES` rows for the income and expense accounts, so importers can reconcile the file.
+    /// The file is encoded as PC8 (code page 437) as the format requires.
+    pub fn export_sie<W: Write>(
+        &self,
+        hash: EntryHash,
+        company: &str,
+        generated: NaiveDate,
+        mut output: W,
+    ) -> Result<()> {
+        let fiscal_year = self.fiscal_year(hash.clone())?;
+        let mut entries = vec![];
+        for item in self.history(&hash) {
+            if let (_, entry @ Entry::Entry { .. }) = item? {
+                entries.push(entry);
+            }
+        }
+        entries.reverse();
+
+        let date = |d: Option<NaiveDate>| d.map(|d| d.format("%Y%m%d").to_string());
+        let mut sie = String::new();
+        let mut line = |l: String| {
+            sie.push_str(&l);
+            sie.push_str("\r\n");
+        };
+        line("#FLAGGA 0".to_string());
+        line("#FORMAT PC8".to_string());
+        line("#SIETYP 4".to_string());
+        line(format!(
+            "#PROGRAM {} {}",
+            quote("bok"),
+            quote(env!("CARGO_PKG_VERSION"))
+        ));
+        line(format!("#GEN {}", generated.format("%Y%m%d")));
+        line(format!("#FNAMN {}", quote(company)));
+        line(format!(
+            "#RAR 0 {} {}",
+            date(fiscal_year.from).unwrap_or_default(),
+            date(fiscal_year.to).unwrap_or_default()
+        ));
+
+        let mut accounts: BTreeMap<String, (String, Option<AccountClass>)> = self
+            .chart()
+            .iter()
+            .map(|a| (a.number.clone(), (a.name.clone(), Some(a.class))))
+            .collect();
+        let mut opening_balance = TrialBalance::new();
+        let mut balance = TrialBalance::new();
+        for entry in &entries {
+            let Entry::Entry { lines, .. } = entry else {
+                continue;
+            };
+            for l in lines {
+                accounts
+                    .entry(l.account.clone())
+                    .or_insert_with(|| (String::new(), AccountClass::from_bas(&l.account)));
+                if entry.is_opening_balance() {
+                    opening_balance.add_line(l)?;
+                }
+                balance.add_line(l)?;
+            }
+        }
+        for (number, (name, class)) in &accounts {
+            line(format!("#KONTO {} {}", field(number), quote(name)));
+            if let Some(class) = class {
+                let kind = match class {
+                    AccountClass::Asset => "T",
+                    AccountClass::Liability | AccountClass::Equity => "S",
+                    AccountClass::Income => "I",
+                    AccountClass::Expense => "K",
+                };
+                line(format!("#KTYP {} {}", field(number), kind));
+            }
+        }
+        for (account, totals) in opening_balance.accounts() {
+            line(format!("#IB 0 {} {}", field(account), totals.net()));
+        }
+        for (account, totals) in balance.accounts() {
+            if let Some(AccountClass::Asset | AccountClass::Liability | AccountClass::Equity) =
+                accounts[account].1
+            {
+                line(format!("#UB 0 {} {}", field(account), totals.net()));
+            }
+        }
+        for (account, totals) in balance.accounts() {
+            if let Some(AccountClass::Income | AccountClass::Expense) = accounts[account].1 {
+                line(format!("#RES 0 {} {}", field(account), totals.net()));
+            }
+        }
+
+        for entry in entries.iter().filter(|e| !e.is_opening_balance()) {
+            let Entry::Entry {
+                event_date,
+                name,
+                description,
+                lines,
+                ..
+            } = entry
+            else {
+                continue;
+            };
+            let (series, number) = split_voucher_name(name);
+            line(format!(
+                "#VER {} {} {} {}",
+                field(series),
+                field(number),
+                event_date.format("%Y%m%d"),
+                quote(description)
+            ));
+            line("{".to_string());
+            for l in lines {
+                let amount = match l.side {
+                    Side::Debit => l.amount,
+                    Side::Credit => l.amount.checked_neg().expect("amounts are positive"),
+                };
+                let mut trans = format!("   #TRANS {} {{}} {}", field(&l.account), amount);
+                if let Some(text) = &l.description {
+                    trans += &format!(" \"\" {}", quote(text));
+                }
+                line(trans);
+            }
+            line("}".to_string());
+        }
+        output.write_all(&cp437::encode(&sie))?;
+        Ok(output.flush()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use super::*;
+    use crate::{Amount, ChartOfAccounts, EntryLine};
+
+    fn line(account: &str, amount: i64, side: Side, description: Option<&str>) -> EntryLine {
+        let description = description.map(str::to_string);
+        EntryLine::new(account, Amount::from_minor(amount), side, description)
+    }
+
+    #[test]
+    fn exports_sample_ledger() -> Result<()> {
+        let dir = env::temp_dir().join("bok_sie_export");
+        let _ = fs::remove_dir_all(&dir);
+        let mut ledger = Ledger::init(2025, dir.clone())?;
+        ledger.set_chart(ChartOfAccounts::from_bas_csv(
+            "1930;Företagskonto\n2081;Aktiekapital\n2641;Debiterad ingående moms\n\
+             3001;Försäljning inom Sverige, 25 % moms\n5410;Förbrukningsinventarier\n",
+        )?)?;
+        let date = |m, d| NaiveDate::from_ymd_opt(2025, m, d).unwrap();
+        ledger.add_entry_on_date(
+            date(1, 1),
+            Entry::OPENING_BALANCE,
+            "Ingående balanser",
+            vec![
+                line("1930", 2500000, Side::Debit, None),
+                line("2081", 2500000, Side::Credit, None),
+            ],
+        )?;
+        ledger.add_entry_on_date(
+            date(1, 15),
+            "A1",
+            "Kontorsmaterial \"Clas Ohlson\"",
+            vec![
+                line("5410", 40000, Side::Debit, Some("Pennor")),
+                line("2641", 10000, Side::Debit, None),
+                line("1930", 50000, Side::Credit, None),
+            ],
+        )?;
+        ledger.add_entry_on_date(
+            date(2, 3),
+            "A2",
+            "Försäljning",
+            vec![
+                line("1930", 125050, Side::Debit, None),
+                line("3001", 125050, Side::Credit, None),
+            ],
+        )?;
+        let mut output = vec![];
+        let head = ledger.from_ref("HEAD")?;
+        ledger.export_sie(head, "Exempel AB", date(12, 31), &mut output)?;
+        fs::remove_dir_all(&dir)?;
+        assert_eq!(output, include_bytes!("../../tests/data/export.se"));
+        Ok(())
+    }
+}
@@ -0,0 +1,326 @@
+use std::{collections::BTreeMap, fs::remove_dir_all, io::Read, path::PathBuf};
+
+use chrono::NaiveDate;
+
+use super::cp437;
+use crate::{
+    Account, AccountClass, Amount, ChartOfAccounts, Entry, EntryLine, Error, Ledger, Result, Side,
+    VoucherNumber,
+};
+
+/// A `#VER` and its `#TRANS` rows.
+struct Voucher {
+    name: String,
+    date: NaiveDate,
+    description: String,
+    lines: Vec<EntryLine>,
+}
+
+impl Ledger {
+    /// Creates a new ledger at `location` from a SIE type 4 file.
+    ///
+    /// The `#RAR 0` fiscal year becomes the Origin, `#KONTO`/`#KTYP` the chart of accounts and
+    /// `#IB 0` rows an opening balance entry. Every `#VER` is then recorded in file order with
+    /// its series and number as name, i.e. `A12`, and its transaction date as event date.
+    pub fn import_sie<R: Read>(mut input: R, location: PathBuf) -> Result<Self> {
+        let mut bytes = vec![];
+        input.read_to_end(&mut bytes)?;
+        let content = cp437::decode(&bytes);
+
+        let mut fiscal_year = None;
+        // #KTYP may come after #KONTO, so accounts are classified once the file is read.
+        let mut names = BTreeMap::new();
+        let mut types = BTreeMap::new();
+        let mut opening_balance = vec![];
+        let mut vouchers: Vec<Voucher> = vec![];
+        let mut in_voucher = false;
+        for (number, line) in content.lines().enumerate() {
+            let bad = |msg: &str| {
+                Error::InvalidData(format!("SIE line {}: {}: {}", number + 1, msg, line.trim()))
+            };
+            let fields = split_fields(line).map_err(&bad)?;
+            let field = |i: usize| fields.get(i).map(String::as_str).unwrap_or("");
+            let date = |i: usize| {
+                NaiveDate::parse_from_str(field(i), "%Y%m%d").map_err(|_| bad("invalid date"))
+            };
+            let amount = |i: usize| {
+                field(i)
+                    .parse::<Amount>()
+                    .map_err(|_| bad("invalid amount"))
+            };
+            match field(0) {
+                "{" => in_voucher = true,
+                "}" => in_voucher = false,
+                "#RAR" if field(1) == "0" => fiscal_year = Some((date(2)?, date(3)?)),
+                "#KONTO" => {
+                    names.insert(field(1).to_string(), field(2).to_string());
+                }
+                "#KTYP" => {
+                    let class = match (field(2), AccountClass::from_bas(field(1))) {
+                        ("T", _) => AccountClass::Asset,
+                        ("S", Some(AccountClass::Equity)) => AccountClass::Equity,
+                        ("S", _) => AccountClass::Liability,
+                        ("I", _) => AccountClass::Income,
+                        ("K", _) => AccountClass::Expense,
+                        _ => return Err(bad("unknown account type")),
+                    };
+                    types.insert(field(1).to_string(), class);
+                }
+                "#IB" if field(1) == "0" => {
+                    let amount = amount(3)?;
+                    if !amount.is_zero() {
+                        opening_balance.push(signed_line(field(2), amount, None));
+                    }
+                }
+                "#VER" => vouchers.push(Voucher {
+                    name: format!("{}{}", field(1), field(2)),
+                    date: date(3)?,
+                    description: field(4).to_string(),
+                    lines: vec![],
+                }),
+                "#TRANS" if in_voucher => {
+                    let voucher = vouchers
+                        .last_mut()
+                        .ok_or_else(|| bad("#TRANS outside #VER"))?;
+                    let description = Some(field(5).to_string()).filter(|d| !d.is_empty());
+                    voucher
+                        .lines
+                        .push(signed_line(field(1), amount(3)?, description));
+                }
+                // Everything else, i.e. #RTRANS and #BTRANS that are followed by or replaced
+                // with #TRANS rows, carries no information we keep.
+                _ => {}
+            }
+        }
+
+        let (year_start, year_end) = fiscal_year
+            .ok_or_else(|| Error::InvalidData("SIE file has no #RAR 0 fiscal year".to_string()))?;
+        for line in opening_balance
+            .iter()
+            .chain(vouchers.iter().flat_map(|v| &v.lines))
+        {
+            names.entry(line.account.clone()).or_default();
+        }
+        let mut chart = ChartOfAccounts::new();
+        for (number, name) in names {
+            let class = types.get(&number).copied();
+            let class = class
+                .or_else(|| AccountClass::from_bas(&number))
+                .ok_or_else(|| {
+                    Error::InvalidData(format!(
+                        "Can't tell the class of account {} in SIE file, it has no #KTYP",
+                        number
+                    ))
+                })?;
+            chart.insert(Account {
+                number,
+                name,
+                class,
+                vat_code: None,
+                active: true,
+            });
+        }
+        let mut ledger = Ledger::init_fiscal_year(year_start, year_end, location.clone())?;
+        // A half imported ledger would block the next attempt, so it's removed on failure.
+        match ledger.record_sie(chart, year_start, opening_balance, vouchers) {
+            Ok(()) => Ok(ledger),
+            Err(e) => {
+                let _ = remove_dir_all(&location);
+                Err(e)
+            }
+        }
+    }
+
+    /// Records what was read from a SIE file in a newly created ledger.
+    fn record_sie(
+        &mut self,
+        chart: ChartOfAccounts,
+        year_start: NaiveDate,
+        opening_balance: Vec<EntryLine>,
+        vouchers: Vec<Voucher>,
+    ) -> Result<()> {
+        if !chart.is_empty() {
+            self.set_chart(chart)?;
+        }
+        self.import_series(
+            vouchers
+                .iter()
+                .filter_map(|v| VoucherNumber::parse(&v.name)),
+        )?;
+        if !opening_balance.is_empty() {
+            self.add_entry_on_date(
+                year_start,
+                Entry::OPENING_BALANCE,
+                "Ingående balanser",
+                opening_balance,
+            )?;
+        }
+        for voucher in vouchers {
+            self.add_entry_on_date(
+                voucher.date,
+                &voucher.name,
+                &voucher.description,
+                voucher.lines,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Turns a SIE amount, where debits are positive, into a line.
+fn signed_line(account: &str, amount: Amount, description: Option<String>) -> EntryLine {
+    let (amount, side) = match amount.checked_neg() {
+        Some(negated) if amount.is_negative() => (negated, Side::Credit),
+        _ => (amount, Side::Debit),
+    };
+    EntryLine::new(account, amount, side, description)
+}
+
+/// Splits a SIE line into fields.
+///
+/// Fields are separated by whitespace, quoted fields may contain whitespace and escaped
+/// quotes, and an object list `{...}` is kept as a single field.
+fn split_fields(line: &str) -> std::result::Result<Vec<String>, &'static str> {
+    let mut fields = vec![];
+    let mut chars = line.trim().chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => continue,
+            '"' => {
+                let mut field = String::new();
+                loop {
+                    match chars.next() {
+                        Some('\\') => field.extend(chars.next()),
+                        Some('"') => break,
+                        Some(c) => field.push(c),
+                        None => return Err("unterminated quote"),
+                    }
+                }
+                fields.push(field);
+            }
+            '{' if !fields.is_empty() => {
+                let mut field = String::from("{");
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => field.push(c),
+                        None => return Err("unterminated object list"),
+                    }
+                }
+                field.push('}');
+                fields.push(field);
+            }
+            c => {
+                let mut field = String::from(c);
+                while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                    field.push(c);
+                }
+                fields.push(field);
+            }
+        }
+    }
+    Ok(fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use super::*;
+    use crate::LogFilter;
+
+    #[test]
+    fn round_trips_exported_file() -> Result<()> {
+        let sample = include_bytes!("../../tests/data/export.se");
+        let dir = env::temp_dir().join("bok_sie_round_trip");
+        let _ = fs::remove_dir_all(&dir);
+        let ledger = Ledger::import_sie(&sample[..], dir.clone())?;
+        let mut output = vec![];
+        let head = ledger.from_ref("HEAD")?;
+        let generated = NaiveDate::from_ymd_opt(2025, 12, 31).unwrap();
+        ledger.export_sie(head, "Exempel AB", generated, &mut output)?;
+        fs::remove_dir_all(&dir)?;
+        assert_eq!(output, sample);
+        Ok(())
+    }
+
+    #[test]
+    fn imports_file_from_other_software() -> Result<()> {
+        let sample = include_bytes!("../../tests/data/import.se");
+        let dir = env::temp_dir().join("bok_sie_import");
+        let _ = fs::remove_dir_all(&dir);
+        let ledger = Ledger::import_sie(&sample[..], dir.clone())?;
+        let head = ledger.from_ref("HEAD")?;
+        let log = ledger.show_log(head, &LogFilter::default())?;
+        let balance = ledger.trial_balance(ledger.from_ref("HEAD")?, crate::Period::all())?;
+        let mut reopened = Ledger::from_dir(dir.clone())?;
+        assert_eq!(reopened.series().keys().collect::<Vec<_>>(), ["A", "B"]);
+        assert_eq!(reopened.next_voucher("B")?, VoucherNumber::new("B", 8));
+        assert_eq!(reopened.next_voucher("A")?, VoucherNumber::new("A", 2));
+        assert_eq!(reopened.fsck()?, vec![]);
+        fs::remove_dir_all(&dir)?;
+
+        assert_eq!(ledger.chart().name("2640"), Some("Ingående moms"));
+        assert_eq!(
+            ledger.chart().get("2640").unwrap().class,
+            AccountClass::Liability
+        );
+        assert!(log.contains("2025-03-02: B7, Hyra \"mars\""));
+        assert_eq!(log.lines().count(), 4);
+        let bank = balance.get("1930").unwrap();
+        assert_eq!(bank.net(), Amount::from_minor(1000000 - 625000 + 250000));
+        Ok(())
+    }
+
+    #[test]
+    fn classifies_accounts_after_reading_types() -> Result<()> {
+        let dir = env::temp_dir().join("bok_sie_account_types");
+        let _ = fs::remove_dir_all(&dir);
+        let sample = "#RAR 0 20250101 20251231\n\
+                      #KONTO 9100 \"Eget konto\"\n#KONTO 2081 Aktiekapital\n\
+                      #KTYP 2081 S\n#KTYP 9100 T\n\
+                      #IB 0 9100 100.00\n#IB 0 2081 -100.00\n";
+        let ledger = Ledger::import_sie(sample.as_bytes(), dir.clone())?;
+        let account = ledger.chart().get("9100").unwrap();
+        assert_eq!(
+            (account.name.as_str(), account.class),
+            ("Eget konto", AccountClass::Asset)
+        );
+        assert_eq!(
+            ledger.chart().get("2081").unwrap().class,
+            AccountClass::Equity
+        );
+        fs::remove_dir_all(&dir)?;
+
+        let sample = "#RAR 0 20250101 20251231\n#KONTO 9100 \"Eget konto\"\n";
+        assert!(
+            Ledger::import_sie(sample.as_bytes(), dir.clone())
+                .is_err_and(|e| e.to_string().contains("account 9100"))
+        );
+        assert!(!dir.exists());
+        Ok(())
+    }
+
+    #[test]
+    fn removes_ledger_when_import_fails() -> Result<()> {
+        let dir = env::temp_dir().join("bok_sie_failed_import");
+        let _ = fs::remove_dir_all(&dir);
+        let sample = "#RAR 0 20250101 20251231\n\
+                      #VER A 1 20250101 Fel\n{\n#TRANS 1930 {} 100.00\n#TRANS 3001 {} -90.00\n}\n";
+        assert!(matches!(
+            Ledger::import_sie(sample.as_bytes(), dir.clone()),
+            Err(Error::Unbalanced(_))
+        ));
+        assert!(!dir.exists());
+
+        let sample = include_bytes!("../../tests/data/import.se");
+        Ledger::import_sie(&sample[..], dir.clone())?;
+        assert!(matches!(
+            Ledger::import_sie(&sample[..], dir.clone()),
+            Err(Error::LedgerExists(_))
+        ));
+        assert!(Ledger::from_dir(dir.clone()).is_ok());
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,18 @@
+//! Reading and writing SIE type 4 files, the Swedish standard for exchanging books.
+mod cp437;
+mod export;
+mod import;
+
+/// Quotes a field if it's empty or contains characters that would split it.
+fn field(s: &str) -> String {
+    if s.is_empty() || s.contains(|c: char| c.is_whitespace() || "\"{}\\".contains(c)) {
+        quote(s)
+    } else {
+        s.to_string()
+    }
+}
+
+/// Quotes a text field, escaping quotes and backslashes.
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
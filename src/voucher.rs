@@ -0,0 +1,174 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display},
+};
+
+use crate::{Error, Result};
+
+/// The series used when none is configured.
+pub const DEFAULT_SERIES: &str = "A";
+
+/// The series and number of a voucher, i.e. `A12`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct VoucherNumber {
+    pub series: String,
+    pub number: u32,
+}
+
+impl VoucherNumber {
+    pub fn new(series: &str, number: u32) -> Self {
+        Self {
+            series: series.to_string(),
+            number,
+        }
+    }
+
+    /// Parses an entry name like `A12`, names without a trailing number aren't vouchers.
+    pub fn parse(name: &str) -> Option<Self> {
+        let (series, number) = split_voucher_name(name);
+        Some(Self::new(series, number.parse().ok()?))
+    }
+}
+
+impl Display for VoucherNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.series, self.number)
+    }
+}
+
+/// Splits a voucher name like `A12` into its series `A` and number `12`.
+///
+/// Names without a trailing number are returned as a series without a number.
+pub(crate) fn split_voucher_name(name: &str) -> (&str, &str) {
+    let split = name.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+    name.split_at(split)
+}
+
+/// Checks that a series name can be told apart from the number following it.
+pub(crate) fn check_series_name(series: &str) -> Result<()> {
+    if series.is_empty() || series.contains(|c: char| c.is_ascii_digit() || c.is_whitespace()) {
+        return Err(Error::InvalidInput(format!(
+            "Invalid series name '{}', use letters only",
+            series
+        )));
+    }
+    Ok(())
+}
+
+/// A break in the numbering of a voucher series.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SeriesProblem {
+    /// A number between the first and the last voucher of the series is missing.
+    Gap { series: String, number: u32 },
+    /// More than one entry has the same voucher number.
+    Duplicate {
+        voucher: VoucherNumber,
+        entries: Vec<String>,
+    },
+}
+
+impl Display for SeriesProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SeriesProblem::Gap { series, number } => {
+                write!(f, "voucher {}{} is missing", series, number)
+            }
+            SeriesProblem::Duplicate { voucher, entries } => write!(
+                f,
+                "voucher {} is used by {} entries: {}",
+                voucher,
+                entries.len(),
+                entries.join(", ")
+            ),
+        }
+    }
+}
+
+/// Finds gaps and duplicates among the voucher numbers of the given entries.
+///
+/// Every series is expected to be numbered without breaks from 1, or from the number after
+/// its counter in `used_before` for a series that was numbered before it was imported.
+pub(crate) fn series_problems<'a>(
+    vouchers: impl IntoIterator<Item = (VoucherNumber, &'a str)>,
+    used_before: &BTreeMap<String, u32>,
+) -> Vec<SeriesProblem> {
+    let mut numbers: BTreeMap<String, BTreeMap<u32, Vec<String>>> = BTreeMap::new();
+    for (voucher, hash) in vouchers {
+        numbers
+            .entry(voucher.series)
+            .or_default()
+            .entry(voucher.number)
+            .or_default()
+            .push(hash.to_string());
+    }
+    let mut problems = vec![];
+    for (series, used) in numbers {
+        let first = used_before.get(&series).map_or(1, |counter| counter + 1);
+        let last = used.keys().next_back().copied().unwrap_or(0);
+        for number in first..=last {
+            match used.get(&number) {
+                None => problems.push(SeriesProblem::Gap {
+                    series: series.clone(),
+                    number,
+                }),
+                Some(entries) if entries.len() > 1 => problems.push(SeriesProblem::Duplicate {
+                    voucher: VoucherNumber::new(&series, number),
+                    entries: entries.clone(),
+                }),
+                Some(_) => {}
+            }
+        }
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_voucher_names() {
+        assert_eq!(
+            VoucherNumber::parse("A12"),
+            Some(VoucherNumber::new("A", 12))
+        );
+        assert_eq!(
+            VoucherNumber::parse("LF3"),
+            Some(VoucherNumber::new("LF", 3))
+        );
+        assert_eq!(VoucherNumber::parse("IB"), None);
+    }
+
+    #[test]
+    fn finds_gaps_and_duplicates() {
+        let vouchers = [("A1", "a"), ("A3", "b"), ("B1", "c"), ("B1", "d")]
+            .map(|(name, hash)| (VoucherNumber::parse(name).unwrap(), hash));
+        assert_eq!(
+            series_problems(vouchers, &BTreeMap::new()),
+            vec![
+                SeriesProblem::Gap {
+                    series: "A".to_string(),
+                    number: 2
+                },
+                SeriesProblem::Duplicate {
+                    voucher: VoucherNumber::new("B", 1),
+                    entries: vec!["c".to_string(), "d".to_string()],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn imported_series_start_after_their_counter() {
+        let vouchers = [("B7", "a"), ("B9", "b")]
+            .map(|(name, hash)| (VoucherNumber::parse(name).unwrap(), hash));
+        let used_before = BTreeMap::from([("B".to_string(), 6)]);
+        assert_eq!(
+            series_problems(vouchers, &used_before),
+            vec![SeriesProblem::Gap {
+                series: "B".to_string(),
+                number: 8
+            }]
+        );
+    }
+}
@@ -0,0 +1,327 @@
+use std::collections::BTreeMap;
+
+use chrono::{Months, Utc};
+
+use crate::{
+    Amount, Entry, EntryLine, Error, Ledger, Period, Result, Side, TrialBalance, ledger::EntryHash,
+};
+
+/// Account the result of the year is closed against (Årets resultat, resultaträkningen).
+pub(crate) const RESULT: &str = "8999";
+
+/// Equity account holding the result of the year (Årets resultat, balansräkningen).
+const YEAR_RESULT: &str = "2099";
+
+/// Equity account the result is carried over to in the next year (Balanserad vinst eller
+/// förlust).
+const RETAINED_EARNINGS: &str = "2091";
+
+impl Ledger {
+    /// Closes the fiscal year at HEAD and opens the next one, returning the new HEAD.
+    ///
+    /// The result of the year, the net of the income and expense accounts 3xxx–8xxx, is
+    /// booked to 2099 against 8999 as a voucher in `series` on the last day of the year. A
+    /// new chain then starts with an Origin following the last entry of the year and an
+    /// opening balance entry (IB) with the balances of the balance sheet accounts 1xxx–2xxx,
+    /// where the result of the year is moved from 2099 to 2091. All these accounts must be
+    /// usable in the chart, which is checked before anything is written.
+    ///
+    /// The new fiscal year starts the day after the closed one and is twelve months long,
+    /// so a broken fiscal year from July to June is followed by another one.
+    pub fn new_year(&mut self, series: &str) -> Result<EntryHash> {
+        let head = self.from_ref("HEAD")?;
+        let fiscal_year = self.fiscal_year(head.clone())?;
+        let (Some(_), Some(last_day)) = (fiscal_year.from, fiscal_year.to) else {
+            unreachable!("the fiscal year of an Origin has both bounds");
+        };
+        // The next fiscal year is as long as a normal one, ending the day before the
+        // same date a year later.
+        let first_day = last_day.succ_opt().ok_or(Error::Overflow)?;
+        let next_last_day = first_day
+            .checked_add_months(Months::new(12))
+            .and_then(|day| day.pred_opt())
+            .ok_or(Error::Overflow)?;
+
+        // Everything is built before the first write so nothing but I/O can fail halfway.
+        let unusable: Vec<&str> = [RESULT, YEAR_RESULT, RETAINED_EARNINGS]
+            .into_iter()
+            .filter(|account| self.chart().check_usable(account).is_err())
+            .collect();
+        if !unusable.is_empty() {
+            return Err(Error::InvalidInput(format!(
+                "Can't close the fiscal year without the accounts {}, add them to the chart",
+                unusable.join(", ")
+            )));
+        }
+        let balance = self.trial_balance(head.clone(), Period::all())?;
+        let result = net(&balance, |digit| ('3'..='8').contains(&digit))?;
+        let closing = if result.is_zero() {
+            None
+        } else {
+            let lines = vec![
+                line(RESULT, result.checked_neg().ok_or(Error::Overflow)?)?,
+                line(YEAR_RESULT, result)?,
+            ];
+            let name = self.next_voucher(series)?.to_string();
+            let description = "Årets resultat";
+            let entry = self.new_entry(last_day, &name, description, lines)?;
+            Some((entry, format!("record: {} {}", name, description)))
+        };
+        let closed = match &closing {
+            Some((entry, _)) => entry.serialize(std::io::empty())?,
+            None => head.as_ref().to_string(),
+        };
+
+        // The closing entry moves the result to 2099, which is carried over to 2091.
+        let mut opening = BTreeMap::from([(RETAINED_EARNINGS, result)]);
+        for (account, totals) in balance.accounts() {
+            if account.starts_with(['1', '2']) {
+                let account = match account {
+                    YEAR_RESULT => RETAINED_EARNINGS,
+                    account => account,
+                };
+                let net: &mut Amount = opening.entry(account).or_default();
+                *net = net.checked_add(totals.net()).ok_or(Error::Overflow)?;
+            }
+        }
+        let lines = opening
+            .into_iter()
+            .filter(|(_, net)| !net.is_zero())
+            .map(|(account, net)| line(account, net))
+            .collect::<Result<Vec<_>>>()?;
+        self.check_accounts(&lines)?;
+
+        let origin = Entry::Origin {
+            timestamp: Utc::now(),
+            start: first_day,
+            end: next_last_day,
+            previous_year: Some(closed.clone()),
+        };
+        let description = "Ingående balanser";
+        let opening_balance = if lines.is_empty() {
+            None
+        } else {
+            let origin_hash = origin.serialize(std::io::empty())?;
+            Some(Entry::new(
+                first_day,
+                Entry::OPENING_BALANCE,
+                description,
+                lines,
+                &origin_hash,
+            )?)
+        };
+
+        // The whole year end is written under one lock, so no other process can write
+        // between the closing entry, the Origin and the opening balances.
+        let lock = self.lock()?;
+        if let Some((entry, message)) = closing {
+            self.advance_head_locked(&lock, entry, &message)?;
+        }
+        let message = format!("new-year: closed {} at {}", fiscal_year, &closed[..6]);
+        let mut hash = self.advance_head_locked(&lock, origin, &message)?;
+        if let Some(entry) = opening_balance {
+            let message = format!("record: {} {}", Entry::OPENING_BALANCE, description);
+            hash = self.advance_head_locked(&lock, entry, &message)?;
+        }
+        Ok(hash)
+    }
+}
+
+/// Net of the accounts whose number starts with a digit matching `first`.
+fn net(balance: &TrialBalance, first: impl Fn(char) -> bool) -> Result<Amount> {
+    balance
+        .accounts()
+        .filter(|(account, _)| account.chars().next().is_some_and(&first))
+        .try_fold(Amount::ZERO, |sum, (_, totals)| {
+            sum.checked_add(totals.net())
+        })
+        .ok_or(Error::Overflow)
+}
+
+/// A line moving `net`, debit minus credit, to the account.
+fn line(account: &str, net: Amount) -> Result<EntryLine> {
+    let side = if net.is_negative() {
+        Side::Credit
+    } else {
+        Side::Debit
+    };
+    let amount = net.checked_abs().ok_or(Error::Overflow)?;
+    Ok(EntryLine::new(account, amount, side, None))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::ChartOfAccounts;
+
+    fn lines(debit: &str, credit: &str, amount: i64) -> Vec<EntryLine> {
+        vec![
+            EntryLine::new(debit, Amount::from_minor(amount), Side::Debit, None),
+            EntryLine::new(credit, Amount::from_minor(amount), Side::Credit, None),
+        ]
+    }
+
+    #[test]
+    fn closes_year_and_carries_balances() -> Result<()> {
+        let dir = env::temp_dir().join("bok_new_year");
+        let _ = fs::remove_dir_all(&dir);
+        let mut ledger = Ledger::init(2025, dir.clone())?;
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        ledger.add_voucher_on_date(
+            date(2025, 3, 1),
+            "A",
+            "Försäljning",
+            lines("1930", "3001", 100000),
+        )?;
+        ledger.add_voucher_on_date(
+            date(2025, 4, 1),
+            "A",
+            "Pennor",
+            lines("5410", "1930", 30000),
+        )?;
+
+        let head = ledger.new_year("A")?;
+        let closing = match ledger.get_entry(&ledger.from_ref("HEAD~1")?)?.clone() {
+            Entry::Origin {
+                start,
+                end,
+                previous_year: Some(previous_year),
+                ..
+            } => {
+                assert_eq!((start, end), (date(2026, 1, 1), date(2026, 12, 31)));
+                ledger.from_ref(&previous_year)?
+            }
+            entry => panic!("expected an Origin following 2025, got {:?}", entry),
+        };
+        match ledger.get_entry(&closing)?.clone() {
+            Entry::Entry {
+                name,
+                event_date,
+                lines: closing_lines,
+                ..
+            } => {
+                assert_eq!(name, "A3");
+                assert_eq!(event_date, date(2025, 12, 31));
+                assert_eq!(closing_lines, lines("8999", "2099", 70000));
+            }
+            entry => panic!("expected the closing entry, got {:?}", entry),
+        }
+        let opening = ledger.get_entry(&head)?.clone();
+        assert!(opening.is_opening_balance());
+        assert!(matches!(
+            opening,
+            Entry::Entry { event_date, lines: l, .. }
+                if event_date == date(2026, 1, 1) && l == lines("1930", "2091", 70000)
+        ));
+
+        // Refs step back from the Origin into the closed year.
+        assert_eq!(ledger.from_ref("HEAD~2")?.as_ref(), closing.as_ref());
+        let pens = ledger.from_ref("HEAD~3")?;
+        assert!(matches!(ledger.get_entry(&pens)?, Entry::Entry { name, .. } if name == "A2"));
+        let sale = ledger.from_ref("HEAD@{2025-03-31}")?;
+        assert_eq!(ledger.from_ref("HEAD~4")?.as_ref(), sale.as_ref());
+        assert!(matches!(
+            ledger.from_ref("HEAD~6"),
+            Err(Error::RefNotFound(_))
+        ));
+
+        assert_eq!(ledger.next_voucher("A")?.to_string(), "A1");
+        assert!(matches!(
+            ledger.add_voucher_on_date(date(2025, 12, 31), "A", "Sen", lines("5410", "1930", 1)),
+            Err(Error::OutsideFiscalYear { .. })
+        ));
+        assert_eq!(ledger.fsck()?, vec![]);
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn follows_broken_fiscal_year() -> Result<()> {
+        let dir = env::temp_dir().join("bok_new_broken_year");
+        let _ = fs::remove_dir_all(&dir);
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let mut ledger =
+            Ledger::init_fiscal_year(date(2025, 7, 1), date(2026, 6, 30), dir.clone())?;
+        ledger.add_voucher_on_date(
+            date(2026, 3, 1),
+            "A",
+            "Försäljning",
+            lines("1930", "3001", 100000),
+        )?;
+        assert!(matches!(
+            ledger.add_voucher_on_date(date(2025, 6, 30), "A", "Tidig", lines("5410", "1930", 1)),
+            Err(Error::OutsideFiscalYear { .. })
+        ));
+
+        let head = ledger.new_year("A")?;
+        assert_eq!(
+            ledger.fiscal_year(head.clone())?,
+            Period::new(Some(date(2026, 7, 1)), Some(date(2027, 6, 30)))
+        );
+        assert!(matches!(
+            ledger.get_entry(&head)?,
+            Entry::Entry { event_date, .. } if *event_date == date(2026, 7, 1)
+        ));
+        assert!(matches!(
+            Ledger::init_fiscal_year(date(2025, 7, 1), date(2027, 1, 1), dir.join("long")),
+            Err(Error::InvalidInput(_))
+        ));
+        assert_eq!(ledger.fsck()?, vec![]);
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn checks_accounts_before_writing() -> Result<()> {
+        let dir = env::temp_dir().join("bok_new_year_chart");
+        let _ = fs::remove_dir_all(&dir);
+        let mut ledger = Ledger::init(2025, dir.clone())?;
+        let chart = "1930;Företagskonto\n2099;Årets resultat\n3001;Försäljning\n";
+        ledger.set_chart(ChartOfAccounts::from_bas_csv(chart)?)?;
+        let date = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+        ledger.add_voucher_on_date(date, "A", "Försäljning", lines("1930", "3001", 100))?;
+
+        assert!(matches!(
+            ledger.new_year("A"),
+            Err(Error::InvalidInput(message)) if message.contains("accounts 8999, 2091,")
+        ));
+        let chart = format!("{}2091;Balanserad vinst\n8999;Årets resultat\n", chart);
+        let mut chart = ChartOfAccounts::from_bas_csv(&chart)?;
+        chart.get_mut("1930").unwrap().active = false;
+        ledger.set_chart(chart)?;
+        assert!(matches!(
+            ledger.new_year("A"),
+            Err(Error::InactiveAccount { number, .. }) if number == "1930"
+        ));
+        assert_eq!(ledger.reflog()?.len(), 2);
+        assert_eq!(ledger.next_voucher("A")?.to_string(), "A2");
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn writes_nothing_while_locked() -> Result<()> {
+        let dir = env::temp_dir().join("bok_new_year_locked");
+        let _ = fs::remove_dir_all(&dir);
+        let mut ledger = Ledger::init(2025, dir.clone())?;
+        let date = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+        ledger.add_voucher_on_date(date, "A", "Försäljning", lines("1930", "3001", 100))?;
+        let head = ledger.from_ref("HEAD")?;
+
+        let lock = ledger.lock()?;
+        assert!(matches!(ledger.new_year("A"), Err(Error::Locked(_))));
+        drop(lock);
+        let reopened = Ledger::from_dir(dir.clone())?;
+        assert_eq!(reopened.from_ref("HEAD")?.as_ref(), head.as_ref());
+        assert_eq!(ledger.reflog()?.len(), 2);
+
+        ledger.new_year("A")?;
+        assert_eq!(ledger.reflog()?.len(), 5);
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}